@@ -0,0 +1,23 @@
+extern crate rsreddit;
+
+use rsreddit::async_client::AsyncReddit;
+use rsreddit::client::Reddit;
+
+#[tokio::main]
+async fn main() {
+    // Fetch several threads concurrently. Needs the `async` feature:
+    // cargo run --example async_hot_threads --features async
+    let reddit = AsyncReddit::from(Reddit::default().build());
+    let (first, second) = tokio::join!(
+        reddit.thread_by_permalink("/r/rust/comments/hwuvmf/swc_now_works_with_stable_rustc/"),
+        reddit.thread_by_permalink(
+            "/r/rust/comments/hv78hc/show_rrust_a_tiny_unix_shell_from_c_to_rust_from/"
+        ),
+    );
+    for thread in [first, second].iter() {
+        match thread {
+            Ok(t) => println!("{:?}", t.link.title),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
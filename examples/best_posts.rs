@@ -1,9 +1,11 @@
 extern crate rsreddit;
 
+use std::sync::Arc;
+
 use rsreddit::client::Reddit;
-use rsreddit::model::sort_time::SortTime;
 use rsreddit::oauth2::{AuthorizationTime, RedditApiScope, RedditOAuth};
-use rsreddit::util::convert_scope_vec_to_string;
+use rsreddit::scope::ScopeSet;
+use rsreddit::token_store::FileTokenStore;
 
 fn main() {
     // Show hot posts for authorized user
@@ -15,24 +17,29 @@ fn main() {
     // * reddit.best()
 
     // OAuth2 Authorization stuff
-    let mut reddit_oauth = RedditOAuth::default().build();
-    let mut scopes = Vec::new();
-    scopes.push(RedditApiScope::read);
-    let scope_string = convert_scope_vec_to_string(&scopes);
-    // Authenticate user. Returns bearer token
-    let bearer_token =
-        reddit_oauth.authorize_client(&scope_string, Some(AuthorizationTime::permanent));
-    if let Some(token) = bearer_token {
-        // Reddit client object
-        let reddit = Reddit::default().bearer_token(token).build();
+    // Tokens are kept in `tokens.json`, so the user only has to authorize once
+    let store = Arc::new(FileTokenStore::new("tokens.json"));
+    let mut reddit_oauth = RedditOAuth::default().token_store(store.clone()).build();
+    let scopes = ScopeSet::from(vec![RedditApiScope::read]);
+    // Authenticate user, unless a token of a previous run is stored. Returns bearer token
+    let bearer_token = match reddit_oauth.stored_token() {
+        Ok(Some(token)) => Ok(token),
+        _ => reddit_oauth.authorize_client(&scopes, Some(AuthorizationTime::permanent)),
+    };
+    if let Ok(token) = bearer_token {
+        // Reddit client object. Saves refreshed tokens to the store
+        let reddit = Reddit::default()
+            .bearer_token(token)
+            .token_store(store)
+            .build();
         // Query hot posts of /r/rust with a limit of 20 posts
         let answer = reddit
-            .hot(Some("/r/rust"), "", "", 0, 20, false, false)
+            .hot(Some("/r/rust"), None, None, 0, 20, false, false)
             .unwrap();
         // Get "after" tag from Listing to browse the following posts
-        let after = answer.data.after.unwrap();
+        let after = answer.data.after;
         // Query hot posts after previous ones
-        let next_answer = reddit.hot(Some("/r/rust"), &after, "", 0, 20, false, false);
+        let next_answer = reddit.hot(Some("/r/rust"), after.as_ref(), None, 0, 20, false, false);
         // Do stuff with Listing
         match next_answer {
             Ok(a) => println!("{:?}", a),
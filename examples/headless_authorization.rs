@@ -0,0 +1,23 @@
+extern crate rsreddit;
+
+use std::io;
+
+use rsreddit::oauth2::{AuthorizationTime, RedditApiScope, RedditOAuth};
+use rsreddit::scope::ScopeSet;
+
+fn main() {
+    // Authorize without a local browser, e.g. on a server reached over SSH
+    let mut reddit_oauth = RedditOAuth::default().build();
+    let scopes = ScopeSet::from(vec![RedditApiScope::identity, RedditApiScope::read]);
+    let url = reddit_oauth.manual_authorization_url(&scopes, Some(AuthorizationTime::permanent));
+    println!("Open this URL in any browser and allow access:\n{}", url);
+    println!("Paste the URL you were redirected to, even if the page did not load:");
+    let mut redirect_url = String::new();
+    if io::stdin().read_line(&mut redirect_url).is_err() {
+        return;
+    }
+    match reddit_oauth.authorize_with_redirect_url(redirect_url.trim()) {
+        Ok(_) => println!("Authenticated!"),
+        Err(e) => println!("{}", e),
+    }
+}
@@ -0,0 +1,27 @@
+extern crate rsreddit;
+
+use std::env;
+
+use rsreddit::client::Reddit;
+use rsreddit::oauth2::RedditOAuth;
+use rsreddit::scope::ScopeSet;
+
+fn main() {
+    // Script apps authenticate with the credentials of the account they are registered for.
+    // CLIENT_ID and CLIENT_SECRET have to belong to a script app
+    let username = env::var("REDDIT_USERNAME").unwrap_or_default();
+    let password = env::var("REDDIT_PASSWORD").unwrap_or_default();
+    // Current 2FA code, only necessary if two-factor authentication is enabled
+    let otp = env::var("REDDIT_OTP").ok();
+    let mut reddit_oauth = RedditOAuth::default().build();
+    match reddit_oauth.authorize_script(&username, &password, otp.as_deref(), &ScopeSet::all()) {
+        Ok(token) => {
+            let reddit = Reddit::default().bearer_token(token).build();
+            match reddit.hot(Some("/r/rust"), None, None, 0, 5, false, false) {
+                Ok(listing) => println!("{:?}", listing),
+                Err(e) => println!("{}", e),
+            }
+        }
+        Err(e) => println!("{}", e),
+    }
+}
@@ -0,0 +1,962 @@
+//! Async counterparts of `Reddit` and `RedditOAuth`.
+//! Requests are sent through an `AsyncHttpTransport`, by default an `AsyncCurlTransport`, which drives
+//! all transfers with curl's multi interface. Many requests can be awaited concurrently without occupying a thread each.
+//! Rate limit and retry delays use tokio's timer, so the runtime needs the time driver enabled.
+//! Only available with the `async` feature
+
+//Built in libraries
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+//Third party libraries
+use tokio::task;
+use tokio::time;
+
+//Own stuff
+use super::async_transport::{default_async_transport, AsyncHttpTransport};
+use super::client::{
+    bearer_header, continue_thread_permalink, continued_replies, parse_comment_response,
+    parse_more_children, parse_scope_descriptions, parse_thread, unexpanded_stubs, Reddit,
+    MORE_CHILDREN_BATCH,
+};
+use super::error::{OAuthError, RedditError};
+use super::model::account::Account;
+use super::model::fullname::{Fullname, ThingId, ThingKind};
+use super::model::listing::{nest_comments, Listing};
+use super::model::scope_description::ScopeDescription;
+use super::model::sort_time::SortTime;
+use super::model::thing::Thing;
+use super::model::thread::Thread;
+use super::model::token::{OAuthToken, TokenInfo};
+use super::oauth2::{
+    authorize_in_browser, parse_refresh_response, parse_token_response, refresh_request,
+    AuthorizationTime, RedditApiScope, RedditOAuth,
+};
+use super::scope::ScopeSet;
+use super::transport::{HttpRequest, HttpResponse};
+use super::util::parse_json;
+
+/// Time between attempts to take the refresh lock while another client refreshes the bearer token
+const REFRESH_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Async Reddit API client. Exposes the endpoints of `Reddit` as futures.
+/// Shares bearer token, token refreshes, rate limit and retry policy with the wrapped `Reddit`
+///
+/// # Example
+/// ```no_run
+/// use rsreddit::async_client::AsyncReddit;
+/// use rsreddit::client::Reddit;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let reddit = AsyncReddit::from(Reddit::default().build());
+///     let (first, second) = tokio::join!(
+///         reddit.thread_by_permalink("/r/rust/comments/hwuvmf/"),
+///         reddit.thread_by_permalink("/r/rust/comments/hv78hc/"),
+///     );
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AsyncReddit {
+    reddit: Arc<Reddit>,
+    transport: Arc<dyn AsyncHttpTransport>,
+}
+
+impl AsyncReddit {
+    /// Sets transport used to send requests, e.g. a `MockTransport` in tests
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::Arc;
+    /// use rsreddit::async_client::AsyncReddit;
+    /// use rsreddit::client::Reddit;
+    /// use rsreddit::transport::MockTransport;
+    /// let reddit = AsyncReddit::from(Reddit::default().build())
+    ///                      .transport(Arc::new(MockTransport::default()));
+    /// ```
+    pub fn transport(mut self, transport: Arc<dyn AsyncHttpTransport>) -> AsyncReddit {
+        self.transport = transport;
+        self
+    }
+
+    /// Wrapped blocking client, e.g. to read `current_token`
+    pub fn blocking(&self) -> &Reddit {
+        &self.reddit
+    }
+
+    /// Query comments for a thread. See `Reddit::thread_by_permalink`
+    pub async fn thread_by_permalink(&self, permalink: &str) -> Result<Thread, RedditError> {
+        let answer = match self.read_token().await? {
+            Some(token) => {
+                let request = self.reddit.thread_request(permalink, true);
+                self.send_authorized(request, &token).await?
+            }
+            None => {
+                self.send(self.reddit.thread_request(permalink, false))
+                    .await?
+            }
+        };
+        parse_thread(&answer.text())
+    }
+
+    /// Query comments for many threads at once. Requests are sent concurrently. See `Reddit::threads_by_permalinks`
+    pub async fn threads_by_permalinks(
+        &self,
+        permalinks: &[&str],
+    ) -> Vec<Result<Thread, RedditError>> {
+        let token = match self.read_token().await {
+            Ok(token) => token,
+            Err(e) => return permalinks.iter().map(|_| Err(e.clone())).collect(),
+        };
+        let pending: Vec<_> = permalinks
+            .iter()
+            .map(|p| {
+                let request = match &token {
+                    Some(token) => self
+                        .reddit
+                        .thread_request(p, true)
+                        .header(&bearer_header(token)),
+                    None => self.reddit.thread_request(p, false),
+                };
+                let client = self.clone();
+                task::spawn(async move {
+                    let answer = client.send(request).await?;
+                    parse_thread(&answer.text())
+                })
+            })
+            .collect();
+        let mut threads = Vec::with_capacity(pending.len());
+        for thread in pending {
+            threads.push(
+                thread
+                    .await
+                    .unwrap_or_else(|e| Err(RedditError::Transport(e.to_string()))),
+            );
+        }
+        threads
+    }
+
+    /// Comments left out of a comment tree in `more` stubs. See `Reddit::more_children`
+    pub async fn more_children(
+        &self,
+        link: &Fullname,
+        children: &[ThingId],
+        limit_children: bool,
+    ) -> Result<Vec<Thing>, RedditError> {
+        link.expect_kind(&[ThingKind::LINK])?;
+        let token = self.read_token().await?;
+        let mut things = Vec::new();
+        for batch in children.chunks(MORE_CHILDREN_BATCH) {
+            let answer = match &token {
+                Some(token) => {
+                    let request =
+                        self.reddit
+                            .more_children_request(link, batch, limit_children, true);
+                    self.send_authorized(request, token).await?
+                }
+                None => {
+                    let request =
+                        self.reddit
+                            .more_children_request(link, batch, limit_children, false);
+                    self.send(request).await?
+                }
+            };
+            things.extend(parse_more_children(&answer.text())?);
+        }
+        Ok(things)
+    }
+
+    /// Replaces the `more` and "continue this thread" stubs of `thread` with the comments they stand for.
+    /// See `Reddit::expand_thread`
+    pub async fn expand_thread(&self, thread: &mut Thread) -> Result<(), RedditError> {
+        let link = thread.link.name;
+        let comments = &mut thread.comments;
+        // Stubs reddit returns again are left in place instead of being requested forever
+        let mut expanded = HashSet::new();
+        loop {
+            let stubs = unexpanded_stubs(comments, &expanded);
+            if stubs.is_empty() {
+                return Ok(());
+            }
+            for stub in stubs {
+                let things = if stub.is_continue_thread() {
+                    let permalink = continue_thread_permalink(&link, &stub.parent_id);
+                    let thread = self.thread_by_permalink(&permalink).await?;
+                    continued_replies(thread, &stub.parent_id)
+                } else {
+                    nest_comments(
+                        &stub.parent_id,
+                        self.more_children(&link, &stub.children, false).await?,
+                    )
+                };
+                expanded.insert((stub.name.clone(), stub.parent_id));
+                if comments.replace_more(&stub, things).is_err() {
+                    debug!("Stub {} vanished from the comment tree", stub.name);
+                }
+            }
+        }
+    }
+
+    /// Descriptions of `scopes`, or of all scopes if `None`. See `Reddit::scope_descriptions`
+    pub async fn scope_descriptions(
+        &self,
+        scopes: Option<&ScopeSet>,
+    ) -> Result<Vec<ScopeDescription>, RedditError> {
+        let answer = match self.read_token().await? {
+            Some(token) => {
+                let request = self.reddit.scope_descriptions_request(scopes, true);
+                self.send_authorized(request, &token).await?
+            }
+            None => {
+                let request = self.reddit.scope_descriptions_request(scopes, false);
+                self.send(request).await?
+            }
+        };
+        parse_scope_descriptions(&answer.text())
+    }
+
+    /// Validates the bearer token and reports what it grants. See `Reddit::token_info`
+    pub async fn token_info(&self) -> Result<TokenInfo, RedditError> {
+        let token = self.valid_token().await?.ok_or(RedditError::MissingToken)?;
+        let username = if token.has_scope(RedditApiScope::identity) {
+            Some(self.me().await?.name)
+        } else {
+            let url = format!("{}/api/v1/scopes", self.reddit.oauth_prefix);
+            self.send_authorized(HttpRequest::get(&url), &token).await?;
+            None
+        };
+        // The request may have refreshed the token
+        let token = self
+            .reddit
+            .current_token()
+            .ok_or(RedditError::MissingToken)?;
+        Ok(TokenInfo {
+            username,
+            scopes: token.scopes(),
+            expires_at: token.expires_at(),
+        })
+    }
+
+    /// Account the bearer token acts for. See `Reddit::me`
+    pub async fn me(&self) -> Result<Account, RedditError> {
+        let token = self.token_with_scope(RedditApiScope::identity).await?;
+        let url = format!("{}/api/v1/me", self.reddit.oauth_prefix);
+        let answer = self.send_authorized(HttpRequest::get(&url), &token).await?;
+        parse_json(&answer.text())
+    }
+
+    /// Submit a new comment or reply to a message. See `Reddit::comment`
+    pub async fn comment(&self, parent: &Fullname, text: &str) -> Result<(), RedditError> {
+        let request = self.reddit.comment_request(parent, text)?;
+        let token = self.token_with_scope(RedditApiScope::submit).await?;
+        let answer = self.send_authorized(request, &token).await?;
+        parse_comment_response(&answer.text())
+    }
+
+    /// Get `/best` posts. See `Reddit::best`
+    pub async fn best(
+        &self,
+        subreddit: Option<&str>,
+        after: Option<&Fullname>,
+        before: Option<&Fullname>,
+        count: u32,
+        limit: u32,
+        show: bool,
+        sr_detail: bool,
+    ) -> Result<Listing, RedditError> {
+        self.get_post_by_sorting(
+            "best", subreddit, None, after, before, count, limit, show, sr_detail,
+        )
+        .await
+    }
+
+    /// Get `/hot` posts. See `Reddit::hot`
+    pub async fn hot(
+        &self,
+        subreddit: Option<&str>,
+        after: Option<&Fullname>,
+        before: Option<&Fullname>,
+        count: u32,
+        limit: u32,
+        show: bool,
+        sr_detail: bool,
+    ) -> Result<Listing, RedditError> {
+        self.get_post_by_sorting(
+            "hot", subreddit, None, after, before, count, limit, show, sr_detail,
+        )
+        .await
+    }
+
+    /// Get `/rising` posts. See `Reddit::rising`
+    pub async fn rising(
+        &self,
+        subreddit: Option<&str>,
+        after: Option<&Fullname>,
+        before: Option<&Fullname>,
+        count: u32,
+        limit: u32,
+        show: bool,
+        sr_detail: bool,
+    ) -> Result<Listing, RedditError> {
+        self.get_post_by_sorting(
+            "rising", subreddit, None, after, before, count, limit, show, sr_detail,
+        )
+        .await
+    }
+
+    /// Get `/new` posts. See `Reddit::new`
+    #[allow(clippy::new_ret_no_self)]
+    pub async fn new(
+        &self,
+        subreddit: Option<&str>,
+        after: Option<&Fullname>,
+        before: Option<&Fullname>,
+        count: u32,
+        limit: u32,
+        show: bool,
+        sr_detail: bool,
+    ) -> Result<Listing, RedditError> {
+        self.get_post_by_sorting(
+            "new", subreddit, None, after, before, count, limit, show, sr_detail,
+        )
+        .await
+    }
+
+    /// Get `/top` posts. See `Reddit::top`
+    pub async fn top(
+        &self,
+        subreddit: Option<&str>,
+        t: SortTime,
+        after: Option<&Fullname>,
+        before: Option<&Fullname>,
+        count: u32,
+        limit: u32,
+        show: bool,
+        sr_detail: bool,
+    ) -> Result<Listing, RedditError> {
+        self.get_post_by_sorting(
+            "top",
+            subreddit,
+            Some(t),
+            after,
+            before,
+            count,
+            limit,
+            show,
+            sr_detail,
+        )
+        .await
+    }
+
+    /// Get `/controversial` posts. See `Reddit::controversial`
+    pub async fn controversial(
+        &self,
+        subreddit: Option<&str>,
+        t: SortTime,
+        after: Option<&Fullname>,
+        before: Option<&Fullname>,
+        count: u32,
+        limit: u32,
+        show: bool,
+        sr_detail: bool,
+    ) -> Result<Listing, RedditError> {
+        self.get_post_by_sorting(
+            "controversial",
+            subreddit,
+            Some(t),
+            after,
+            before,
+            count,
+            limit,
+            show,
+            sr_detail,
+        )
+        .await
+    }
+
+    async fn get_post_by_sorting(
+        &self,
+        sorting: &str,
+        subreddit: Option<&str>,
+        t: Option<SortTime>,
+        after: Option<&Fullname>,
+        before: Option<&Fullname>,
+        count: u32,
+        limit: u32,
+        show: bool,
+        sr_detail: bool,
+    ) -> Result<Listing, RedditError> {
+        let request = self.reddit.listing_request(
+            sorting, subreddit, t, after, before, count, limit, show, sr_detail,
+        )?;
+        let token = self.token_with_scope(RedditApiScope::read).await?;
+        let answer = self.send_authorized(request, &token).await?;
+        parse_json(&answer.text())
+    }
+
+    /// Bearer token of the client, if it was granted `scope`
+    async fn token_with_scope(&self, scope: RedditApiScope) -> Result<OAuthToken, RedditError> {
+        let token = self.valid_token().await?.ok_or(RedditError::MissingToken)?;
+        if !token.has_scope(scope) {
+            return Err(RedditError::MissingScope(scope.to_string()));
+        }
+        Ok(token)
+    }
+
+    /// Bearer token of the client, if it was granted `read` scope. Refreshed first, if it expired.
+    /// Like `Reddit`, falls back to `basic_prefix` if the token expired and cannot be refreshed
+    async fn read_token(&self) -> Result<Option<OAuthToken>, RedditError> {
+        match self.reddit.current_token() {
+            Some(token) if token.is_expired() && !token.is_refreshable() => Ok(None),
+            Some(token) if token.has_scope(RedditApiScope::read) => self.valid_token().await,
+            _ => Ok(None),
+        }
+    }
+
+    /// Bearer token of the client. Refreshed first, if it expired.
+    /// An expired token without refresh token is an error
+    async fn valid_token(&self) -> Result<Option<OAuthToken>, RedditError> {
+        match self.reddit.current_token() {
+            Some(token) if token.is_expired() && token.is_refreshable() => {
+                self.refresh(&token).await.map(Some)
+            }
+            Some(token) if token.is_expired() => Err(RedditError::Authorization(
+                "Bearer token expired and has no `refresh_token`".to_owned(),
+            )),
+            token => Ok(token),
+        }
+    }
+
+    /// Requests a new access token for `stale`, unless another request refreshed it in the meantime
+    async fn refresh(&self, stale: &OAuthToken) -> Result<OAuthToken, RedditError> {
+        // The lock is shared with the wrapped `Reddit` and its clones, so only one of them refreshes
+        let _refreshing = loop {
+            if let Some(guard) = self.reddit.refresh_lock().try_lock() {
+                break guard;
+            }
+            time::delay_for(REFRESH_POLL_INTERVAL).await;
+        };
+        if let Some(token) = self.reddit.already_refreshed(stale)? {
+            return Ok(token);
+        }
+        debug!("Refreshing bearer token");
+        let request = refresh_request(&self.reddit.client_credentials, stale);
+        let token = parse_refresh_response(self.send(request).await?, stale)?;
+        // Token stores may write files, which would block the executor thread
+        let reddit = self.reddit.clone();
+        let installed = token.clone();
+        task::spawn_blocking(move || reddit.install_token(&installed))
+            .await
+            .map_err(|e| RedditError::Transport(e.to_string()))?;
+        Ok(token)
+    }
+
+    /// Sends `request` authenticated with `token`.
+    /// If reddit rejects the token, it is refreshed and the request is sent once more
+    async fn send_authorized(
+        &self,
+        request: HttpRequest,
+        token: &OAuthToken,
+    ) -> Result<HttpResponse, RedditError> {
+        match self
+            .send(request.clone().header(&bearer_header(token)))
+            .await
+        {
+            Err(RedditError::HttpStatus { status: 401, .. }) if token.is_refreshable() => {
+                let token = self.refresh(token).await?;
+                self.send(request.header(&bearer_header(&token))).await
+            }
+            result => result,
+        }
+    }
+
+    /// Sends `request` through the transport, respecting the rate limit and retry policy
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, RedditError> {
+        let request = self.reddit.with_user_agent(request);
+        let retry_policy = &self.reddit.retry_policy;
+        let mut attempt = 1;
+        loop {
+            self.acquire().await?;
+            let result = self.transport.send(request.clone()).await;
+            if let Ok(response) = &result {
+                self.reddit.rate_limiter().update(response);
+            }
+            if !retry_policy.should_retry(&request, attempt, &result) {
+                return result?.error_for_status();
+            }
+            let delay = retry_policy.delay(attempt, result.as_ref().ok());
+            debug!(
+                "Attempt {} of {} failed. Retrying in {:?}",
+                attempt, request.url, delay
+            );
+            time::delay_for(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Waits until the rate limit allows another request
+    async fn acquire(&self) -> Result<(), RedditError> {
+        while let Some(wait) = self.reddit.rate_limiter().try_acquire()? {
+            debug!("Rate limit reached. Sleeping for {:?}", wait);
+            time::delay_for(wait).await;
+        }
+        Ok(())
+    }
+}
+
+impl From<Reddit> for AsyncReddit {
+    /// Wraps a built `Reddit` client. Requests are sent through a new `AsyncCurlTransport`,
+    /// not the transport of `reddit`, see `AsyncReddit::transport`
+    fn from(reddit: Reddit) -> AsyncReddit {
+        AsyncReddit {
+            reddit: Arc::new(reddit),
+            transport: default_async_transport(),
+        }
+    }
+}
+
+/// Async OAuth2 client. Exposes the methods of `RedditOAuth` as futures.
+/// The wrapped client is only locked to build requests and to record their outcome,
+/// never while a request or the authorization dialog is pending
+#[derive(Debug, Clone)]
+pub struct AsyncRedditOAuth {
+    oauth: Arc<Mutex<RedditOAuth>>,
+    transport: Arc<dyn AsyncHttpTransport>,
+}
+
+impl AsyncRedditOAuth {
+    /// Sets transport used to send requests, e.g. a `MockTransport` in tests
+    pub fn transport(mut self, transport: Arc<dyn AsyncHttpTransport>) -> AsyncRedditOAuth {
+        self.transport = transport;
+        self
+    }
+
+    /// Snapshot of the wrapped client, e.g. to inspect `oauth_state` and `error_string`
+    pub fn blocking(&self) -> RedditOAuth {
+        self.with(|oauth| oauth.clone())
+    }
+
+    /// Authorize user with the reddit authorization dialog. See `RedditOAuth::authorize_client`
+    pub async fn authorize_client(
+        &self,
+        scope: &ScopeSet,
+        duration: Option<AuthorizationTime>,
+    ) -> Result<OAuthToken, RedditError> {
+        let (authorize_url, callback_url, state) = self.with(|oauth| {
+            (
+                oauth.manual_authorization_url(scope, duration),
+                oauth.callback_url.clone(),
+                oauth.state_string.clone(),
+            )
+        });
+        // The callback server blocks until the user finished the dialog
+        let code = task::spawn_blocking(move || {
+            authorize_in_browser(&authorize_url, &callback_url, &state)
+        })
+        .await
+        .map_err(|e| RedditError::Transport(e.to_string()))
+        .and_then(|code| code);
+        let token = match code {
+            Ok(code) => self.get_initial_access_token(&code).await,
+            Err(e) => Err(e),
+        };
+        self.with(|oauth| oauth.finish_authorization(token))
+    }
+
+    /// URL of the authorization dialog. See `RedditOAuth::manual_authorization_url`
+    pub fn manual_authorization_url(
+        &self,
+        scope: &ScopeSet,
+        duration: Option<AuthorizationTime>,
+    ) -> String {
+        self.with(|oauth| oauth.manual_authorization_url(scope, duration))
+    }
+
+    /// URL of the authorization dialog of a web app, with a new state. See `RedditOAuth::issue_authorization_url`
+    pub async fn issue_authorization_url(
+        &self,
+        scope: &ScopeSet,
+        duration: Option<AuthorizationTime>,
+    ) -> Result<String, RedditError> {
+        self.with(|oauth| oauth.issue_authorization_url(scope, duration))
+    }
+
+    /// Validate callback of a web app and request the token. See `RedditOAuth::complete_authorization`
+    pub async fn complete_authorization(&self, query: &str) -> Result<OAuthToken, RedditError> {
+        let code = self.with(|oauth| oauth.callback_code(query))?;
+        self.exchange_code(&code).await
+    }
+
+    /// Exchange code of an authorization callback for a token. See `RedditOAuth::exchange_code`
+    pub async fn exchange_code(&self, code: &str) -> Result<OAuthToken, RedditError> {
+        let request = self.with(|oauth| oauth.code_request(code));
+        parse_token_response(self.send(request).await?)
+    }
+
+    /// Finish manual authorization. See `RedditOAuth::authorize_with_redirect_url`
+    pub async fn authorize_with_redirect_url(
+        &self,
+        redirect_url: &str,
+    ) -> Result<OAuthToken, RedditError> {
+        let token = match self.with(|oauth| oauth.redirect_code(redirect_url)) {
+            Ok(code) => self.get_initial_access_token(&code).await,
+            Err(e) => Err(e),
+        };
+        self.with(|oauth| oauth.finish_authorization(token))
+    }
+
+    /// Finish manual authorization with code and state. See `RedditOAuth::authorize_with_code`
+    pub async fn authorize_with_code(
+        &self,
+        code: &str,
+        state: &str,
+    ) -> Result<OAuthToken, RedditError> {
+        let token = if self.with(|oauth| oauth.state_string == state) {
+            self.get_initial_access_token(code).await
+        } else {
+            Err(RedditError::OAuth(OAuthError::StateMismatch))
+        };
+        self.with(|oauth| oauth.finish_authorization(token))
+    }
+
+    /// Authorize script app with username and password. See `RedditOAuth::authorize_script`
+    pub async fn authorize_script(
+        &self,
+        username: &str,
+        password: &str,
+        otp: Option<&str>,
+        scope: &ScopeSet,
+    ) -> Result<OAuthToken, RedditError> {
+        let request = self.with(|oauth| oauth.script_request(username, password, otp, scope));
+        self.request_token(request).await
+    }
+
+    /// Obtain application-only token of a confidential client. See `RedditOAuth::authorize_application`
+    pub async fn authorize_application(&self, scope: &ScopeSet) -> Result<OAuthToken, RedditError> {
+        let request = self.with(|oauth| oauth.application_request(scope));
+        self.request_token(request).await
+    }
+
+    /// Obtain application-only token of an installed app. See `RedditOAuth::authorize_installed_client`
+    pub async fn authorize_installed_client(
+        &self,
+        scope: &ScopeSet,
+    ) -> Result<OAuthToken, RedditError> {
+        let request = self.with(|oauth| oauth.installed_client_request(scope));
+        self.request_token(request).await
+    }
+
+    /// Refresh bearer token. See `RedditOAuth::refresh_token`
+    pub async fn refresh_token(&self, to_refresh: &OAuthToken) -> Result<OAuthToken, RedditError> {
+        let token = match self.with(|oauth| oauth.refresh_token_request(to_refresh)) {
+            Ok(request) => self
+                .send(request)
+                .await
+                .and_then(|answer| parse_refresh_response(answer, to_refresh)),
+            Err(e) => Err(e),
+        };
+        self.with(|oauth| oauth.finish_token_request(token))
+    }
+
+    /// Revoke a token. See `RedditOAuth::revoke_token`
+    pub async fn revoke_token(&self, to_revoke: &OAuthToken) -> Result<(), RedditError> {
+        let request = self.with(|oauth| oauth.revoke_request(to_revoke));
+        let answer = self.send(request).await?;
+        self.with(|oauth| oauth.finish_revocation(answer))
+    }
+
+    /// Request access token. See `RedditOAuth::get_initial_access_token`
+    pub async fn get_initial_access_token(&self, state: &str) -> Result<OAuthToken, RedditError> {
+        let token = self.exchange_code(state).await?;
+        self.with(|oauth| oauth.store_token(&token));
+        Ok(token)
+    }
+
+    /// Sends token `request`, updates `oauth_state` and saves the token to the token store
+    async fn request_token(&self, request: HttpRequest) -> Result<OAuthToken, RedditError> {
+        let token = self.send(request).await.and_then(parse_token_response);
+        self.with(|oauth| oauth.finish_token_request(token))
+    }
+
+    /// Sends `request` with the user agent of the application
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, RedditError> {
+        let request = self.with(|oauth| oauth.with_user_agent(request));
+        self.transport.send(request).await
+    }
+
+    /// Runs `call` with the wrapped client locked. Must not block
+    fn with<T, F>(&self, call: F) -> T
+    where
+        F: FnOnce(&mut RedditOAuth) -> T,
+    {
+        call(&mut self.oauth.lock().unwrap())
+    }
+}
+
+impl From<RedditOAuth> for AsyncRedditOAuth {
+    /// Wraps a built `RedditOAuth` client. Requests are sent through a new `AsyncCurlTransport`,
+    /// not the transport of `oauth`, see `AsyncRedditOAuth::transport`
+    fn from(oauth: RedditOAuth) -> AsyncRedditOAuth {
+        AsyncRedditOAuth {
+            oauth: Arc::new(Mutex::new(oauth)),
+            transport: default_async_transport(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::async_transport::ResponseFuture;
+    use crate::oauth2::OAuthState;
+    use crate::retry::RetryPolicy;
+    use crate::test_fixtures::{thread_json, token};
+    use crate::token_store::TokenStore;
+    use crate::transport::MockTransport;
+    use tokio::sync::oneshot;
+
+    fn reddit(mock: &Arc<MockTransport>) -> AsyncReddit {
+        AsyncReddit::from(Reddit::default().build()).transport(mock.clone())
+    }
+
+    fn expired_token() -> OAuthToken {
+        let mut expired = token("read");
+        expired.refresh_token = "refresh".to_owned();
+        expired.created_at -= 3600;
+        expired
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(404, "{}");
+        mock.push_response(404, "{}");
+        let reddit = reddit(&mock);
+        let (first, second) = tokio::join!(
+            reddit.thread_by_permalink("/r/rust/comments/abc/"),
+            reddit.thread_by_permalink("/r/rust/comments/def/"),
+        );
+        assert!(first.is_err() && second.is_err());
+        assert_eq!(mock.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_threads_by_permalinks() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(200, &thread_json(&[]));
+        mock.push_response(404, "{}");
+        let threads = reddit(&mock)
+            .threads_by_permalinks(&["/r/rust/comments/a/", "/r/rust/comments/b/"])
+            .await;
+        assert!(threads[0].is_ok());
+        assert!(threads[1].is_err());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_share_refresh() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(
+            200,
+            r#"{"access_token": "new", "token_type": "bearer", "expires_in": 3600, "scope": "read"}"#,
+        );
+        mock.push_response(200, &thread_json(&[]));
+        mock.push_response(200, &thread_json(&[]));
+        let reddit = AsyncReddit::from(Reddit::default().bearer_token(expired_token()).build())
+            .transport(mock.clone());
+        let (first, second) = tokio::join!(
+            reddit.thread_by_permalink("/r/rust/comments/a/"),
+            reddit.thread_by_permalink("/r/rust/comments/b/"),
+        );
+        assert!(first.is_ok() && second.is_ok());
+        let requests = mock.requests();
+        assert_eq!(
+            requests[0].url,
+            "https://www.reddit.com/api/v1/access_token"
+        );
+        assert_eq!(requests[2].headers, vec!["Authorization: bearer new"]);
+        assert_eq!(
+            reddit.blocking().current_token().unwrap().access_token,
+            "new"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refresh_shared_with_blocking_client() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(200, &thread_json(&[]));
+        let reddit = AsyncReddit::from(Reddit::default().bearer_token(expired_token()).build())
+            .transport(mock.clone());
+        // A blocking clone is refreshing the token
+        let refreshing = reddit.blocking().refresh_lock().try_lock().unwrap();
+        let request = {
+            let reddit = reddit.clone();
+            task::spawn(async move { reddit.thread_by_permalink("/r/rust/comments/a/").await })
+        };
+        time::delay_for(Duration::from_millis(50)).await;
+        assert!(mock.requests().is_empty());
+        let mut refreshed = token("read");
+        refreshed.access_token = "new".to_owned();
+        reddit.blocking().install_token(&refreshed);
+        drop(refreshing);
+        request.await.unwrap().unwrap();
+        // The token of the blocking clone is used instead of refreshing again
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].headers, vec!["Authorization: bearer new"]);
+    }
+
+    /// Records the threads tokens are saved on
+    #[derive(Debug, Default)]
+    struct ThreadRecordingStore {
+        saved_on: Mutex<Vec<std::thread::ThreadId>>,
+    }
+
+    impl TokenStore for ThreadRecordingStore {
+        fn load(&self, _: &str, _: &str) -> Result<Option<OAuthToken>, RedditError> {
+            Ok(None)
+        }
+
+        fn save(&self, _: &str, _: &str, _: &OAuthToken) -> Result<(), RedditError> {
+            self.saved_on
+                .lock()
+                .unwrap()
+                .push(std::thread::current().id());
+            Ok(())
+        }
+
+        fn clear(&self, _: &str, _: &str) -> Result<(), RedditError> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refreshed_token_saved_off_executor() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(
+            200,
+            r#"{"access_token": "new", "token_type": "bearer", "expires_in": 3600, "scope": "read"}"#,
+        );
+        mock.push_response(200, &thread_json(&[]));
+        let store = Arc::new(ThreadRecordingStore::default());
+        let reddit = AsyncReddit::from(
+            Reddit::default()
+                .bearer_token(expired_token())
+                .token_store(store.clone())
+                .build(),
+        )
+        .transport(mock.clone());
+        assert!(reddit
+            .thread_by_permalink("/r/rust/comments/a/")
+            .await
+            .is_ok());
+        let saved_on = store.saved_on.lock().unwrap();
+        assert_eq!(saved_on.len(), 1);
+        // The test runs on the executor thread of the basic scheduler
+        assert_ne!(saved_on[0], std::thread::current().id());
+    }
+
+    #[tokio::test]
+    async fn test_retry_server_errors() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(503, "<html>Maintenance</html>");
+        mock.push_response(200, &thread_json(&[]));
+        let reddit = AsyncReddit::from(
+            Reddit::default()
+                .retry_policy(RetryPolicy::default().base_delay(Duration::from_millis(1)))
+                .build(),
+        )
+        .transport(mock.clone());
+        assert!(reddit
+            .thread_by_permalink("/r/rust/comments/a/")
+            .await
+            .is_ok());
+        assert_eq!(mock.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_comment_without_token() {
+        let reddit = reddit(&Arc::new(MockTransport::default()));
+        match reddit.comment(&"t3_abc".parse().unwrap(), "Hello").await {
+            Err(RedditError::MissingToken) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_expired_application_token() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(200, &thread_json(&[]));
+        let mut expired = token("*");
+        expired.created_at -= 3600;
+        let reddit = AsyncReddit::from(Reddit::default().bearer_token(expired).build())
+            .transport(mock.clone());
+        assert!(reddit
+            .thread_by_permalink("/r/rust/comments/a/")
+            .await
+            .is_ok());
+        assert!(mock.requests()[0].headers.is_empty());
+        match reddit.comment(&"t3_abc".parse().unwrap(), "Hello").await {
+            Err(RedditError::Authorization(_)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_authorize_with_code_state_mismatch() {
+        let mock = Arc::new(MockTransport::default());
+        let oauth = AsyncRedditOAuth::from(RedditOAuth::default().state_string("expected").build())
+            .transport(mock.clone());
+        assert!(oauth.authorize_with_code("code", "forged").await.is_err());
+        assert!(mock.requests().is_empty());
+    }
+
+    /// Holds back responses until the gate is opened
+    #[derive(Debug, Default)]
+    struct GatedTransport {
+        inner: MockTransport,
+        gate: Mutex<Option<oneshot::Receiver<()>>>,
+    }
+
+    impl AsyncHttpTransport for GatedTransport {
+        fn send(&self, request: HttpRequest) -> ResponseFuture {
+            let gate = self.gate.lock().unwrap().take();
+            let response = AsyncHttpTransport::send(&self.inner, request);
+            Box::pin(async move {
+                if let Some(gate) = gate {
+                    gate.await.ok();
+                }
+                response.await
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_oauth_unlocked_while_request_pending() {
+        let (open, gate) = oneshot::channel();
+        let transport = Arc::new(GatedTransport::default());
+        *transport.gate.lock().unwrap() = Some(gate);
+        transport.inner.push_response(
+            200,
+            r#"{"access_token": "new", "token_type": "bearer", "expires_in": 3600, "scope": "read"}"#,
+        );
+        let oauth =
+            AsyncRedditOAuth::from(RedditOAuth::default().build()).transport(transport.clone());
+        let refreshing = {
+            let oauth = oauth.clone();
+            task::spawn(async move { oauth.refresh_token(&expired_token()).await })
+        };
+        while transport.inner.requests().is_empty() {
+            time::delay_for(Duration::from_millis(1)).await;
+        }
+        // Neither call waits for the pending refresh
+        let url = oauth.manual_authorization_url(&ScopeSet::from(RedditApiScope::read), None);
+        assert!(url.starts_with("https://www.reddit.com/api/v1/authorize?"));
+        assert_ne!(oauth.blocking().oauth_state, OAuthState::AUTHORIZED);
+        open.send(()).unwrap();
+        let token = refreshing.await.unwrap().unwrap();
+        assert_eq!(token.refresh_token, "refresh");
+        assert_eq!(oauth.blocking().oauth_state, OAuthState::AUTHORIZED);
+    }
+}
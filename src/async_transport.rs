@@ -0,0 +1,237 @@
+//! Async HTTP transports used by `AsyncReddit` and `AsyncRedditOAuth`.
+//! `AsyncCurlTransport` is used by default. `MockTransport` answers async requests as well.
+//! Only available with the `async` feature
+
+//Built in libraries
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+//Third party libraries
+use curl::multi::{Easy2Handle, Multi};
+use tokio::sync::oneshot;
+
+//Own stuff
+use super::curl_utils::{self, Collector, Handle};
+use super::error::RedditError;
+use super::transport::{HttpRequest, HttpResponse, HttpTransport, MockTransport};
+
+/// Upper bound for the time a new request waits while other transfers are running
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Response of an `AsyncHttpTransport`
+pub type ResponseFuture = Pin<Box<dyn Future<Output = Result<HttpResponse, RedditError>> + Send>>;
+
+/// Sends HTTP requests to reddit without blocking the calling task
+pub trait AsyncHttpTransport: Debug + Send + Sync {
+    fn send(&self, request: HttpRequest) -> ResponseFuture;
+}
+
+/// Request waiting for the driver thread, and where to deliver its response
+type Job = (
+    HttpRequest,
+    oneshot::Sender<Result<HttpResponse, RedditError>>,
+);
+
+/// Default async transport, sending requests with curl's multi interface.
+/// One background thread drives all transfers of the transport, however many requests are awaited at once.
+/// Curl handles are kept after a request finished, so later requests reuse their open connections
+pub struct AsyncCurlTransport {
+    jobs: Mutex<Sender<Job>>,
+    max_parallel: usize,
+}
+
+impl Default for AsyncCurlTransport {
+    fn default() -> AsyncCurlTransport {
+        AsyncCurlTransport::new(8)
+    }
+}
+
+impl AsyncCurlTransport {
+    /// Creates transport running at most `max_parallel` transfers at once. Further requests are queued.
+    /// At most this many idle handles are kept
+    pub fn new(max_parallel: usize) -> AsyncCurlTransport {
+        let max_parallel = max_parallel.max(1);
+        let (jobs, queue) = mpsc::channel();
+        // The thread ends once the transport is dropped and all transfers are done
+        thread::spawn(move || drive(queue, max_parallel));
+        AsyncCurlTransport {
+            jobs: Mutex::new(jobs),
+            max_parallel,
+        }
+    }
+}
+
+impl Debug for AsyncCurlTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("AsyncCurlTransport")
+            .field("max_parallel", &self.max_parallel)
+            .finish()
+    }
+}
+
+impl AsyncHttpTransport for AsyncCurlTransport {
+    fn send(&self, request: HttpRequest) -> ResponseFuture {
+        let (response, receiver) = oneshot::channel();
+        let queued = self.jobs.lock().unwrap().send((request, response));
+        Box::pin(async move {
+            queued.map_err(|_| RedditError::Transport("Curl driver thread stopped".to_owned()))?;
+            receiver
+                .await
+                .map_err(|_| RedditError::Transport("Transfer did not finish".to_owned()))?
+        })
+    }
+}
+
+/// Returns the async transport used if no other transport is set
+pub fn default_async_transport() -> Arc<dyn AsyncHttpTransport> {
+    Arc::new(AsyncCurlTransport::default())
+}
+
+impl AsyncHttpTransport for MockTransport {
+    /// Answers right away with the next canned response
+    fn send(&self, request: HttpRequest) -> ResponseFuture {
+        let response = HttpTransport::send(self, &request);
+        Box::pin(async move { response })
+    }
+}
+
+/// Runs the transfers of an `AsyncCurlTransport` until all senders of `queue` are dropped
+fn drive(queue: Receiver<Job>, max_parallel: usize) {
+    let multi = Multi::new();
+    let mut waiting: VecDeque<Job> = VecDeque::new();
+    let mut running: Vec<(Easy2Handle<Collector>, oneshot::Sender<_>)> = Vec::new();
+    let mut idle: Vec<Handle> = Vec::new();
+    let mut connected = true;
+    loop {
+        // Nothing to do. Sleep until the next request arrives
+        if running.is_empty() && waiting.is_empty() {
+            if !connected {
+                return;
+            }
+            match queue.recv() {
+                Ok(job) => waiting.push_back(job),
+                Err(_) => return,
+            }
+        }
+        while connected {
+            match queue.try_recv() {
+                Ok(job) => waiting.push_back(job),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => connected = false,
+            }
+        }
+        while running.len() < max_parallel {
+            let (request, response) = match waiting.pop_front() {
+                Some(job) => job,
+                None => break,
+            };
+            let mut handle = idle.pop().unwrap_or_else(curl_utils::new_handle);
+            if let Err(e) = curl_utils::prepare(&mut handle, &request) {
+                response.send(Err(e)).ok();
+                idle.push(handle);
+                continue;
+            }
+            match multi.add2(handle) {
+                Ok(easy_handle) => running.push((easy_handle, response)),
+                Err(e) => {
+                    response.send(Err(RedditError::from(e))).ok();
+                }
+            }
+        }
+        if let Err(e) = multi.perform() {
+            warn!("Performing curl transfers failed: {}", e);
+        }
+        // Hand finished transfers to their futures
+        let mut finished = Vec::new();
+        multi.messages(|message| {
+            for (index, (easy_handle, _)) in running.iter().enumerate() {
+                if let Some(result) = message.result_for2(easy_handle) {
+                    finished.push((index, result));
+                }
+            }
+        });
+        // Highest index first, so `swap_remove` does not move transfers which are still to be removed
+        finished.sort_by_key(|(index, _)| Reverse(*index));
+        for (index, result) in finished {
+            let (easy_handle, response) = running.swap_remove(index);
+            let result = match multi.remove2(easy_handle) {
+                Ok(mut handle) => {
+                    let result = result
+                        .map_err(RedditError::from)
+                        .and_then(|_| curl_utils::take_response(&mut handle));
+                    idle.push(handle);
+                    result
+                }
+                Err(e) => Err(RedditError::from(e)),
+            };
+            // The future may have been dropped in the meantime
+            response.send(result).ok();
+        }
+        idle.truncate(max_parallel);
+        if !running.is_empty() {
+            if let Err(e) = multi.wait(&mut [], POLL_INTERVAL) {
+                warn!("Waiting for curl transfers failed: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serves `count` requests on a local port, answering with the requested path after `delay`
+    fn serve(count: usize, delay: Duration) -> String {
+        let server = Arc::new(tiny_http::Server::http("127.0.0.1:0").unwrap());
+        let address = format!("http://{}", server.server_addr());
+        for _ in 0..count {
+            let server = server.clone();
+            thread::spawn(move || {
+                let request = server.recv().unwrap();
+                thread::sleep(delay);
+                let path = request.url().to_string();
+                request
+                    .respond(tiny_http::Response::from_string(path))
+                    .unwrap();
+            });
+        }
+        address
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_transfers() {
+        let address = serve(4, Duration::from_millis(300));
+        let transport = AsyncCurlTransport::new(4);
+        let start = std::time::Instant::now();
+        let (first, second, third, fourth) = tokio::join!(
+            transport.send(HttpRequest::get(&format!("{}/1", address))),
+            transport.send(HttpRequest::get(&format!("{}/2", address))),
+            transport.send(HttpRequest::get(&format!("{}/3", address))),
+            transport.send(HttpRequest::get(&format!("{}/4", address))),
+        );
+        // Transfers overlap instead of running one after another
+        assert!(start.elapsed() < Duration::from_millis(1000));
+        for (path, response) in [first, second, third, fourth].iter().enumerate() {
+            assert_eq!(response.as_ref().unwrap().text(), format!("/{}", path + 1));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transfer_error() {
+        let transport = AsyncCurlTransport::default();
+        match transport
+            .send(HttpRequest::get("http://127.0.0.1:1/"))
+            .await
+        {
+            Err(RedditError::Transport(_)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
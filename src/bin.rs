@@ -1,41 +1,37 @@
 extern crate rsreddit;
 
 use rsreddit::client::Reddit;
-use rsreddit::model::listing::Listing;
-use rsreddit::model::listing::ListingCollection;
+use rsreddit::model::thread::Thread;
 
 fn main() {
     // Get Comment Tree for a thread by its permalink
     let reddit = Reddit::default().build();
-    let tree = reddit
+    let mut thread = reddit
         .thread_by_permalink("/r/rust/comments/hwuvmf/swc_now_works_with_stable_rustc/")
         .unwrap();
-    print_comment_tree(&tree);
+    // Load comments left out of the first answer
+    if let Err(e) = reddit.expand_thread(&mut thread) {
+        println!("Could not load all comments: {}", e);
+    }
+    print_comment_tree(&thread);
 }
 
-fn print_comment_tree(tree: &ListingCollection) {
+fn print_comment_tree(thread: &Thread) {
     //print Thread Title
-    println!(
-        "Title: {}",
-        tree.listings[0].data.children[0]
-            .data
-            .title
-            .as_ref()
-            .unwrap()
-    );
-    //Print comments recursivley
-    travers_comment_tree(&tree.listings[1], 0);
-}
-
-fn travers_comment_tree(comment: &Listing, tab_count: usize) {
-    comment.data.children.iter().for_each(|c| {
-        //Generate right amount if identation
-        let tabs = std::iter::repeat("   ").take(tab_count).collect::<String>();
-        //Print comment
-        println!("{}>{}", tabs, c.data.body.as_ref().unwrap());
-        //Go a level deeper
-        if let Some(replies) = &c.data.replies {
-            travers_comment_tree(replies, tab_count + 1);
-        }
-    });
+    println!("Title: {} ({} comments)", thread.link.title, thread.len());
+    //Print comments in the order they are shown on reddit, indented by their depth
+    for (depth, comment) in thread.depth_first() {
+        let tabs = "   ".repeat(depth);
+        println!("{}>{}", tabs, comment.body);
+    }
+    //Comment with the most replies below it
+    let busiest = thread
+        .top_level()
+        .max_by_key(|comment| thread.subtree_size(&comment.name).unwrap_or_default());
+    if let Some(comment) = busiest {
+        println!(
+            "Most discussed: {} by {}",
+            comment.permalink, comment.author
+        );
+    }
 }
@@ -1,92 +1,242 @@
+//! Local HTTP server receiving the redirect of reddit's authorization dialog
+
 //Built in libraries
-use std::env;
-use std::sync::mpsc;
-use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 //Thrid party libraries
-use dotenv::dotenv;
-use tiny_http::Server;
-use tiny_http::ServerConfig;
-
-use super::util::chomp_http_prefix;
+use tiny_http::{Header, Response, Server};
 
-struct ResponseBox {
-    has_error: bool,
-    error_msg: String,
-    code: String,
-    state: String,
-}
+//Own stuff
+use super::error::{OAuthError, RedditError};
+use super::util::parse_query_string;
 
-pub fn get_browser_response(auth_time: usize, client_state: &str) -> Result<String, String> {
-    let mut error_msg = "";
-    let (tx_authentication, rx) = mpsc::channel();
-    let tx_countdown = mpsc::Sender::clone(&tx_authentication);
-    thread::spawn(move || {
-        dotenv().ok();
-        let mut callback_url = env::var("REDIRECT_URI").unwrap_or_default();
-        // Reddit expects the callback url to start with "http://" or "https://", However, tinyhttp does not
-        // not support url to start with either prefixes, but rather Server::http or Server::https gets called.
-        // thus, the prefix has to be removed if it exists
-        callback_url = chomp_http_prefix(&callback_url);
-        let server = Server::http(callback_url).unwrap();
-        for request in server.incoming_requests() {
-            let request_url = request.url().to_string().clone();
-            let parameter_string: Vec<&str> = request_url.split("/?").collect();
-            if parameter_string.len() <= 1 {
-                continue;
-            };
-            let parameters: Vec<&str> = parameter_string[1].split('&').collect();
-            // Expect state and code parameters
-            if parameters.len() != 2 {
-                error_msg = "Unexpected response from reddit";
-                let auth_box = ResponseBox {
-                    has_error: true,
-                    error_msg: "Unexpected response from reddit".to_string(),
-                    code: "".to_string(),
-                    state: "".to_string(),
-                };
-                tx_authentication.send(auth_box).unwrap();
-            } else {
-                let state: Vec<&str> = parameters[0].split('=').collect();
-                let code: Vec<&str> = parameters[1].split('=').collect();
-                let auth_box = ResponseBox {
-                    has_error: false,
-                    error_msg: "".to_string(),
-                    code: code[1].to_string(),
-                    state: state[1].to_string(),
-                };
-                tx_authentication.send(auth_box).unwrap();
+/// Waits for reddit to redirect the browser to `redirect_uri` and returns the authorization code.
+/// The server listens on host and port of `redirect_uri` and shuts down after the first callback or `timeout`
+/// # Arguments
+///
+/// * `redirect_uri` - Redirect URI of the reddit app, e.g. `http://127.0.0.1:8000`
+/// * `timeout` - Time the user has to finish the authorization dialog
+/// * `client_state` - State string sent with the authorization URL
+pub fn get_browser_response(
+    redirect_uri: &str,
+    timeout: Duration,
+    client_state: &str,
+) -> Result<String, RedditError> {
+    let (address, path) = split_redirect_uri(redirect_uri)?;
+    let server = Server::http(&address).map_err(|e| {
+        RedditError::Authorization(format!(
+            "Could not start callback server on {}: {}",
+            address, e
+        ))
+    })?;
+    let deadline = Instant::now() + timeout;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let request = match server.recv_timeout(remaining) {
+            Ok(Some(request)) => request,
+            Ok(None) => {
+                trace!("Timeout during authentication");
+                return Err(RedditError::OAuth(OAuthError::Timeout));
             }
+            Err(e) => {
+                return Err(RedditError::Authorization(format!(
+                    "Callback server failed: {}",
+                    e
+                )))
+            }
+        };
+        let url = request.url().to_string();
+        let (request_path, query) = match url.find('?') {
+            Some(index) => (&url[..index], &url[index + 1..]),
+            None => (url.as_str(), ""),
+        };
+        // Browsers also ask for e.g. `/favicon.ico`. Only the redirect path ends the wait
+        if request_path.trim_end_matches('/') != path.trim_end_matches('/') {
+            request
+                .respond(Response::from_string("Not found").with_status_code(404))
+                .ok();
+            continue;
         }
-        drop(server);
-    });
-    thread::spawn(move || {
-        for _ in 0..auth_time {
-            thread::sleep(Duration::from_secs(1));
+        let result = parse_callback(query, client_state);
+        // Requests without the state of this authorization did not come from reddit,
+        // e.g. a web page loading the redirect URI. They must not end the user's login
+        if let Err(RedditError::OAuth(OAuthError::StateMismatch)) = &result {
+            warn!("Ignoring authorization callback with unknown state");
+            let body = html_page(
+                "Authorization failed",
+                &OAuthError::StateMismatch.to_string(),
+            );
+            request
+                .respond(Response::from_string(body).with_status_code(400))
+                .ok();
+            continue;
         }
-        let auth_box = ResponseBox {
-            has_error: true,
-            error_msg: "Reached timeout. User did not authorize usage of reddit_api in time"
-                .to_string(),
-            code: "".to_string(),
-            state: "".to_string(),
+        let (status, body) = match &result {
+            Ok(_) => (
+                200,
+                html_page(
+                    "Authorization successful",
+                    "You can close this window and return to the application.",
+                ),
+            ),
+            Err(e) => (400, html_page("Authorization failed", &e.to_string())),
         };
-        trace!("Timeout during authentication");
-        tx_countdown.send(auth_box).unwrap();
-    });
-    let response_box = rx.recv().unwrap();
+        let content_type =
+            Header::from_bytes(&b"Content-Type"[..], &b"text/html; charset=utf-8"[..])
+                .expect("Static header is valid");
+        let response = Response::from_string(body)
+            .with_status_code(status)
+            .with_header(content_type);
+        if let Err(e) = request.respond(response) {
+            warn!("Could not answer authorization callback: {}", e);
+        }
+        return result;
+    }
+}
+
+/// Extracts the authorization code from the query string of a callback, e.g. `state=abc&code=xyz`,
+/// after verifying its state is `client_state`
+pub fn parse_callback(query: &str, client_state: &str) -> Result<String, RedditError> {
+    if callback_state(query) != client_state {
+        return Err(RedditError::OAuth(OAuthError::StateMismatch));
+    }
+    parse_callback_query(query)
+}
+
+/// State of a callback, empty if it has none
+pub fn callback_state(query: &str) -> String {
+    parse_query_string(query)
+        .remove("state")
+        .unwrap_or_default()
+}
+
+/// Extracts the code from the query string of a callback. Its state has to be verified first.
+/// Reddit reports failures as `error=access_denied`, which are turned into `RedditError::OAuth`
+pub fn parse_callback_query(query: &str) -> Result<String, RedditError> {
+    let mut params = parse_query_string(query);
+    if let Some(error) = params.get("error") {
+        return Err(RedditError::OAuth(OAuthError::from_code(error)));
+    }
+    match params.remove("code") {
+        Some(code) if !code.is_empty() => Ok(code),
+        _ => Err(RedditError::OAuth(OAuthError::Other(
+            "Callback contains neither `code` nor `error`".to_owned(),
+        ))),
+    }
+}
 
-    if response_box.has_error {
-        return Err(response_box.error_msg);
+/// Splits `redirect_uri` like `http://127.0.0.1:8000/callback` into address `127.0.0.1:8000` and path `/callback`
+fn split_redirect_uri(redirect_uri: &str) -> Result<(String, String), RedditError> {
+    let rest = redirect_uri.strip_prefix("http://").ok_or_else(|| {
+        RedditError::InvalidArgument(format!(
+            "Callback server only supports `http://` redirect URIs, got `{}`",
+            redirect_uri
+        ))
+    })?;
+    let (address, path) = match rest.find('/') {
+        Some(index) => (&rest[..index], &rest[index..]),
+        None => (rest, "/"),
+    };
+    // Port 80 is implied if none is given
+    let address = if address.contains(':') {
+        address.to_owned()
     } else {
-        if client_state != response_box.state {
-            return Err(
-                "State string of response is not the same. Cannot trust the bearer token."
-                    .to_owned(),
-            );
-        } else {
-            return Ok(response_box.code);
+        format!("{}:80", address)
+    };
+    Ok((address, path.to_owned()))
+}
+
+/// Minimal page shown in the browser after the callback
+fn html_page(title: &str, message: &str) -> String {
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{0}</title></head>\
+         <body><h1>{0}</h1><p>{1}</p></body></html>",
+        html_escape(title),
+        html_escape(message)
+    )
+}
+
+/// Escapes characters with a special meaning in HTML, as error values come from the callback URL
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{CurlTransport, HttpRequest, HttpTransport};
+    use std::thread;
+
+    #[test]
+    fn test_parse_callback() {
+        assert_eq!(parse_callback("code=xyz&state=abc", "abc").unwrap(), "xyz");
+        match parse_callback("state=abc&error=access_denied", "abc") {
+            Err(RedditError::OAuth(e)) => assert_eq!(e, OAuthError::AccessDenied),
+            other => panic!("Unexpected result {:?}", other),
+        }
+        for query in &["state=evil&code=xyz", "state=evil&error=access_denied"] {
+            match parse_callback(query, "abc") {
+                Err(RedditError::OAuth(e)) => assert_eq!(e, OAuthError::StateMismatch),
+                other => panic!("Unexpected result {:?}", other),
+            }
+        }
+        assert!(parse_callback("", "abc").is_err());
+    }
+
+    #[test]
+    fn test_split_redirect_uri() {
+        let (address, path) = split_redirect_uri("http://127.0.0.1:8000").unwrap();
+        assert_eq!((address.as_str(), path.as_str()), ("127.0.0.1:8000", "/"));
+        let (address, path) = split_redirect_uri("http://localhost/callback").unwrap();
+        assert_eq!(
+            (address.as_str(), path.as_str()),
+            ("localhost:80", "/callback")
+        );
+        assert!(split_redirect_uri("https://example.com").is_err());
+    }
+
+    #[test]
+    fn test_get_browser_response() {
+        // Find a free port
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let redirect_uri = format!("http://127.0.0.1:{}/callback", port);
+        let uri = redirect_uri.clone();
+        let server =
+            thread::spawn(move || get_browser_response(&uri, Duration::from_secs(10), "abc"));
+        let transport = CurlTransport::default();
+        let mut response = None;
+        for _ in 0..50 {
+            let favicon = HttpRequest::get(&format!("http://127.0.0.1:{}/favicon.ico", port));
+            if let Ok(answer) = transport.send(&favicon) {
+                assert_eq!(answer.status, 404);
+                // A callback with another state does not end the wait
+                let forged =
+                    HttpRequest::get(&format!("{}?error=access_denied&state=evil", redirect_uri));
+                assert_eq!(transport.send(&forged).unwrap().status, 400);
+                let callback = HttpRequest::get(&format!("{}?code=x%2By&state=abc", redirect_uri));
+                response = Some(transport.send(&callback).unwrap());
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        let response = response.expect("Callback server did not start");
+        assert_eq!(response.status, 200);
+        assert!(response.text().contains("Authorization successful"));
+        assert_eq!(server.join().unwrap().unwrap(), "x+y");
+    }
+
+    #[test]
+    fn test_get_browser_response_timeout() {
+        match get_browser_response("http://127.0.0.1:0", Duration::from_millis(10), "abc") {
+            Err(RedditError::OAuth(e)) => assert_eq!(e, OAuthError::Timeout),
+            other => panic!("Unexpected result {:?}", other),
         }
     }
 }
@@ -1,30 +1,43 @@
 // Built in libraries
-use std::collections::HashMap;
-use std::env;
+use std::collections::{BTreeMap, HashSet};
+use std::convert::TryFrom;
 use std::fmt;
 use std::string::String;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
 
 // Third party libraries
-use curl::easy::{Easy, List};
-use log::{info, warn};
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 // Own includes
-use super::curl_utils::*;
+use super::error::RedditError;
+use super::model::account::Account;
+use super::model::fullname::{Fullname, ThingId, ThingKind};
+use super::model::listing::nest_comments;
 use super::model::listing::Listing;
 use super::model::listing::ListingCollection;
 use super::model::responses::comment_response::CommentResponse;
+use super::model::responses::more_children_response::MoreChildrenResponse;
+use super::model::scope_description::ScopeDescription;
 use super::model::sort_time::SortTime;
-use super::model::thing::Thing;
-use super::model::token::OAuthToken;
-use super::oauth2::OAuthState;
-use super::oauth2::RedditApiScope;
-use super::oauth2::RedditClientCredentials;
-use super::oauth2::RedditOAuth;
+use super::model::thing::{More, Thing};
+use super::model::thread::Thread;
+use super::model::token::{OAuthToken, TokenInfo};
+use super::oauth2::{
+    parse_refresh_response, refresh_request, RedditApiScope, RedditClientCredentials,
+};
+use super::rate_limit::{RateLimitMode, RateLimiter};
+use super::retry::RetryPolicy;
+use super::scope::ScopeSet;
+use super::token_store::{TokenStore, DEFAULT_ACCOUNT};
+use super::transport::{default_transport, HttpRequest, HttpResponse, HttpTransport};
+use super::user_agent::UserAgent;
 use super::util::convert_map_to_string;
 use super::util::insert_json_classname;
-use super::VERSION;
+use super::util::parse_json;
+
+/// Maximum number of comment IDs `/api/morechildren` accepts at once
+pub(crate) const MORE_CHILDREN_BATCH: usize = 100;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// Reddit API client.
@@ -37,11 +50,33 @@ pub struct Reddit {
     pub basic_prefix: String,
     pub oauth_prefix: String,
     pub client_credentials: RedditClientCredentials,
-    pub bearer_token: Option<OAuthToken>,
+    // Shared between clones of the client, so a refreshed token is used by all of them
+    #[serde(with = "shared_token")]
+    bearer_token: Arc<Mutex<Option<OAuthToken>>>,
+    // Held while the bearer token is refreshed, so clones and `AsyncReddit` wrappers send only one refresh request
+    #[serde(skip)]
+    refresh_lock: Arc<RefreshLock>,
+    // Called with the new token after the bearer token was refreshed
+    #[serde(skip)]
+    on_token_refresh: Option<RefreshCallback>,
+    // Store the bearer token is loaded from on `build()` and refreshed tokens are saved to
+    #[serde(skip)]
+    token_store: Option<Arc<dyn TokenStore>>,
+    // Account the token belongs to in `token_store`
+    pub account: String,
+    pub retry_policy: RetryPolicy,
+    // User agent of the application. Falls back to the user agent of this crate if not set
+    pub user_agent: Option<UserAgent>,
+    // Transport used to send requests, `CurlTransport` by default
+    #[serde(skip, default = "default_transport")]
+    transport: Arc<dyn HttpTransport>,
+    // Shared between clones of the client, as they use up the same quota
+    #[serde(skip)]
+    rate_limiter: Arc<RateLimiter>,
     is_built: bool,
 }
 
-impl Reddit {
+impl Default for Reddit {
     /// Creates default Reddit client object, assuming default reddit api endpoint urls
     ///
     /// # Example
@@ -50,16 +85,27 @@ impl Reddit {
     /// let reddit = Reddit::default();
     /// ```
     /// Note: object is in usable state after `build()` is called.
-    pub fn default() -> Reddit {
+    fn default() -> Reddit {
         Reddit {
             authorized_prefix: "https://www.reddit.com/api/v1/".to_owned(),
             basic_prefix: "https://www.reddit.com".to_owned(),
             oauth_prefix: "https://oauth.reddit.com".to_owned(),
             client_credentials: RedditClientCredentials::default(),
-            bearer_token: None,
+            bearer_token: Arc::new(Mutex::new(None)),
+            refresh_lock: Arc::new(RefreshLock::default()),
+            on_token_refresh: None,
+            token_store: None,
+            account: DEFAULT_ACCOUNT.to_owned(),
+            retry_policy: RetryPolicy::default(),
+            user_agent: None,
+            transport: default_transport(),
+            rate_limiter: Arc::new(RateLimiter::default()),
             is_built: false,
         }
     }
+}
+
+impl Reddit {
     /// Sets Client credentials if custom ones are wished
     ///
     /// # Example
@@ -98,17 +144,138 @@ impl Reddit {
         self.basic_prefix = prefix.to_owned();
         self
     }
-    /// Set bearer token
+    /// Sets reddit api url for endpoints requiring a bearer token
+    ///
+    /// # Example
+    /// ```
+    /// use rsreddit::client::Reddit;
+    /// let reddit = Reddit::default()
+    ///                      .oauth_prefix("https://oauth.alternate_reddit.com");
+    /// ```
+    pub fn oauth_prefix(mut self, prefix: &str) -> Reddit {
+        self.oauth_prefix = prefix.to_owned();
+        self
+    }
+    /// Sets user agent identifying your application.
+    /// Reddit throttles clients sharing a generic user agent, so every application should set its own
+    ///
+    /// # Example
+    /// ```
+    /// use rsreddit::client::Reddit;
+    /// use rsreddit::user_agent::UserAgent;
+    /// let reddit = Reddit::default()
+    ///                      .user_agent(UserAgent::new("linux", "my_reddit_bot", "v0.1.0", "my_username"));
+    /// ```
+    pub fn user_agent(mut self, user_agent: UserAgent) -> Reddit {
+        self.user_agent = Some(user_agent);
+        self
+    }
+    /// Set bearer token.
+    /// Once it expires, it is refreshed with its `refresh_token`, if it has one
     pub fn bearer_token(mut self, token: OAuthToken) -> Reddit {
-        self.bearer_token = Some(token.clone());
+        self.bearer_token = Arc::new(Mutex::new(Some(token)));
         self
     }
+    /// Sets function called with the new token whenever the bearer token was refreshed,
+    /// e.g. to persist it for the next start of the application
+    ///
+    /// # Example
+    /// ```
+    /// use rsreddit::client::Reddit;
+    /// let reddit = Reddit::default()
+    ///                      .on_token_refresh(|token| println!("New token expires at {}", token.expires_at()));
+    /// ```
+    pub fn on_token_refresh<F>(mut self, callback: F) -> Reddit
+    where
+        F: Fn(&OAuthToken) + Send + Sync + 'static,
+    {
+        self.on_token_refresh = Some(RefreshCallback(Arc::new(callback)));
+        self
+    }
+    /// Sets transport used to send requests, e.g. a `MockTransport` in tests
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::Arc;
+    /// use rsreddit::client::Reddit;
+    /// use rsreddit::transport::MockTransport;
+    /// let reddit = Reddit::default()
+    ///                      .transport(Arc::new(MockTransport::default()));
+    /// ```
+    pub fn transport(mut self, transport: Arc<dyn HttpTransport>) -> Reddit {
+        self.transport = transport;
+        self
+    }
+    /// Sets behaviour when the rate limit reported by reddit is reached.
+    /// `RateLimitMode::SLEEP` (default) waits until the window resets,
+    /// `RateLimitMode::ERROR` returns `RedditError::RateLimited` instead
+    ///
+    /// # Example
+    /// ```
+    /// use rsreddit::client::Reddit;
+    /// use rsreddit::rate_limit::RateLimitMode;
+    /// let reddit = Reddit::default()
+    ///                      .rate_limit_mode(RateLimitMode::ERROR);
+    /// ```
+    pub fn rate_limit_mode(mut self, mode: RateLimitMode) -> Reddit {
+        self.rate_limiter = Arc::new(RateLimiter::new(mode));
+        self
+    }
+    /// Sets policy for retrying requests failing with `429` or `5xx` status codes.
+    /// GET requests are retried up to 3 times by default, POST requests only if `RetryPolicy.retry_post` is set
+    ///
+    /// # Example
+    /// ```
+    /// use rsreddit::client::Reddit;
+    /// use rsreddit::retry::RetryPolicy;
+    /// let reddit = Reddit::default()
+    ///                      .retry_policy(RetryPolicy::default().max_attempts(5));
+    /// ```
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Reddit {
+        self.retry_policy = retry_policy;
+        self
+    }
+    /// Sets store to load the bearer token from and to save refreshed tokens to.
+    /// The token is loaded on `build()`, unless a bearer token is set explicitly
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::Arc;
+    /// use rsreddit::client::Reddit;
+    /// use rsreddit::token_store::MemoryTokenStore;
+    /// let reddit = Reddit::default()
+    ///                      .token_store(Arc::new(MemoryTokenStore::default()));
+    /// ```
+    pub fn token_store(mut self, token_store: Arc<dyn TokenStore>) -> Reddit {
+        self.token_store = Some(token_store);
+        self
+    }
+    /// Sets account the token belongs to in the token store, `"default"` if not set
+    pub fn account(mut self, account: &str) -> Reddit {
+        self.account = account.to_owned();
+        self
+    }
+    /// Current bearer token, including refreshes done by the client
+    pub fn current_token(&self) -> Option<OAuthToken> {
+        self.bearer_token.lock().unwrap().clone()
+    }
     /// Validates Reddit object in a basic manner.
     /// After calling, object is ready to use
     pub fn build(mut self) -> Reddit {
-        if self.authorized_prefix == "" || self.basic_prefix == "" {
+        if self.authorized_prefix.is_empty()
+            || self.basic_prefix.is_empty()
+            || self.oauth_prefix.is_empty()
+        {
             panic!("No prefixes provided. Cannot communicate with reddit API endpoint!");
         }
+        if self.current_token().is_none() {
+            if let Some(store) = &self.token_store {
+                match store.load(&self.client_credentials.client_id, &self.account) {
+                    Ok(token) => *self.bearer_token.lock().unwrap() = token,
+                    Err(e) => warn!("Could not load bearer token: {}", e),
+                }
+            }
+        }
         self.is_built = true;
         self
     }
@@ -120,52 +287,275 @@ impl Reddit {
     /// Query comments for a thread
     /// # Arguments
     /// * `permalink` permalink string of the thread
-    pub fn thread_by_permalink(&self, permalink: &str) -> Result<ListingCollection, String> {
-        let url = format!("{}{}.json", self.basic_prefix, permalink);
-        let answer = get(&url, "");
-        let augmented_answer = insert_json_classname("listings", &answer);
-        Ok(serde_json::from_str(&augmented_answer).unwrap())
+    ///
+    /// # Returns
+    /// The submission and its comment tree. Left out comments are kept as `more` stubs, see `expand_thread`
+    pub fn thread_by_permalink(&self, permalink: &str) -> Result<Thread, RedditError> {
+        let answer = match self.read_token()? {
+            Some(token) => self.send_authorized(self.thread_request(permalink, true), &token)?,
+            None => self.send(self.thread_request(permalink, false))?,
+        };
+        parse_thread(&answer.text())
     }
 
-    //
-    // `submit` SCOPE
-    //
+    /// Query comments for many threads at once. Requests are sent in parallel if the transport supports it
+    /// # Arguments
+    /// * `permalinks` permalink strings of the threads
+    ///
+    /// # Returns
+    /// One result per permalink, in the same order as `permalinks`.
+    /// An expired bearer token is refreshed before the batch is sent, a token rejected by reddit is not
+    pub fn threads_by_permalinks(&self, permalinks: &[&str]) -> Vec<Result<Thread, RedditError>> {
+        let token = match self.read_token() {
+            Ok(token) => token,
+            Err(e) => return permalinks.iter().map(|_| Err(e.clone())).collect(),
+        };
+        let requests: Vec<HttpRequest> = permalinks
+            .iter()
+            .map(|p| match &token {
+                Some(token) => self.thread_request(p, true).header(&bearer_header(token)),
+                None => self.thread_request(p, false),
+            })
+            .collect();
+        self.send_all(requests)
+            .into_iter()
+            .map(|result| result.and_then(|answer| parse_thread(&answer.text())))
+            .collect()
+    }
 
-    /// Submit a new comment or reply to a message
+    /// Comments left out of a comment tree in `more` stubs
     /// # Arguments
-    /// * `thing_id` fullname of partent thing being replied to. Can be fullname of a link or fullname of a comment ( requires `submit` scope ).
-    /// * `text` Raw markdown text to comment
+    /// * `link` fullname of the link the comments belong to
+    /// * `children` IDs of the comments, e.g. `More.children`. Requested in batches of 100, the most reddit accepts at once
+    /// * `limit_children` if set, only the requested comments are returned, without their replies
     ///
-    /// TODO: add private messaging comments
-    pub fn comment(&self, thing_id: &str, text: &str) -> Result<(), String> {
-        // Check if bearer token is set
-        if let Some(token) = &self.bearer_token {
-            if !token.scope.contains("submit") {
-                return Err("Insufficient scope rights. Need scope: `submit`.".to_owned());
-            } else {
-                let endpoint = "/api/comment";
-                let url = format!("{}{}", self.oauth_prefix, endpoint);
-                let data_header = format!("Authorization: bearer {}", token.access_token);
-                let mut payload_map: HashMap<String, String> = HashMap::new();
-                payload_map.insert("api_type".to_owned(), "json".to_owned());
-                payload_map.insert("text".to_owned(), text.to_owned());
-                payload_map.insert("return_rtjson".to_owned(), "true".to_owned());
-                payload_map.insert("thing_id".to_owned(), thing_id.to_owned());
-                let payload_data = convert_map_to_string(&payload_map);
-                let answer = post(&url, &payload_data, &data_header);
-                let comment_response: CommentResponse = serde_json::from_str(&answer).unwrap();
-                if let Some(err) = comment_response.json {
-                    //TODO: error out of potential many errors
-                    let err_msg = format!("[{}] - {}", err.errors[0][0], err.errors[0][1]);
-                    return Err(err_msg);
+    /// # Returns
+    /// Flat list of comments and further `more` stubs in depth-first order. See `listing::nest_comments` to build a tree of them
+    pub fn more_children(
+        &self,
+        link: &Fullname,
+        children: &[ThingId],
+        limit_children: bool,
+    ) -> Result<Vec<Thing>, RedditError> {
+        link.expect_kind(&[ThingKind::LINK])?;
+        let token = self.read_token()?;
+        let mut things = Vec::new();
+        for batch in children.chunks(MORE_CHILDREN_BATCH) {
+            let answer = match &token {
+                Some(token) => {
+                    let request = self.more_children_request(link, batch, limit_children, true);
+                    self.send_authorized(request, token)?
                 }
+                None => {
+                    self.send(self.more_children_request(link, batch, limit_children, false))?
+                }
+            };
+            things.extend(parse_more_children(&answer.text())?);
+        }
+        Ok(things)
+    }
+
+    /// `/api/morechildren` request for one batch of `children`. Sent to `oauth_prefix` if `authorized`, to `basic_prefix` otherwise
+    pub(crate) fn more_children_request(
+        &self,
+        link: &Fullname,
+        children: &[ThingId],
+        limit_children: bool,
+        authorized: bool,
+    ) -> HttpRequest {
+        let ids: Vec<String> = children.iter().map(ThingId::to_string).collect();
+        let mut params: BTreeMap<&str, String> = BTreeMap::new();
+        params.insert("api_type", "json".to_owned());
+        params.insert("children", ids.join(","));
+        params.insert("limit_children", limit_children.to_string());
+        params.insert("link_id", link.to_string());
+        params.insert("raw_json", "1".to_owned());
+        let query_string = convert_map_to_string(&params);
+        if authorized {
+            HttpRequest::get(&format!(
+                "{}/api/morechildren?{}",
+                self.oauth_prefix, query_string
+            ))
+        } else {
+            HttpRequest::get(&format!(
+                "{}/api/morechildren.json?{}",
+                self.basic_prefix, query_string
+            ))
+        }
+    }
+
+    /// Replaces the `more` and "continue this thread" stubs of `thread`, as returned by `thread_by_permalink`,
+    /// with the comments they stand for, until the comment tree is complete.
+    /// Every stub costs at least one request
+    pub fn expand_thread(&self, thread: &mut Thread) -> Result<(), RedditError> {
+        let link = thread.link.name;
+        let comments = &mut thread.comments;
+        // Stubs reddit returns again are left in place instead of being requested forever
+        let mut expanded = HashSet::new();
+        loop {
+            let stubs = unexpanded_stubs(comments, &expanded);
+            if stubs.is_empty() {
                 return Ok(());
             }
+            for stub in stubs {
+                let things = if stub.is_continue_thread() {
+                    self.continue_thread(&link, &stub.parent_id)?
+                } else {
+                    nest_comments(
+                        &stub.parent_id,
+                        self.more_children(&link, &stub.children, false)?,
+                    )
+                };
+                expanded.insert((stub.name.clone(), stub.parent_id));
+                if comments.replace_more(&stub, things).is_err() {
+                    debug!("Stub {} vanished from the comment tree", stub.name);
+                }
+            }
+        }
+    }
+
+    /// Replies of comment `parent` left out of the tree of `link` by a "continue this thread" stub
+    fn continue_thread(
+        &self,
+        link: &Fullname,
+        parent: &Fullname,
+    ) -> Result<Vec<Thing>, RedditError> {
+        let thread = self.thread_by_permalink(&continue_thread_permalink(link, parent))?;
+        Ok(continued_replies(thread, parent))
+    }
+
+    /// Thread request for `permalink`. Sent to `oauth_prefix` if `authorized`, to `basic_prefix` otherwise
+    pub(crate) fn thread_request(&self, permalink: &str, authorized: bool) -> HttpRequest {
+        if authorized {
+            HttpRequest::get(&format!("{}{}", self.oauth_prefix, permalink))
         } else {
-            return Err("Bearer Token not set. Authorization necessary for commenting".to_owned());
+            HttpRequest::get(&format!("{}{}.json", self.basic_prefix, permalink))
         }
     }
 
+    /// Descriptions of `scopes`, or of all scopes reddit knows if `None`, sorted by id.
+    /// No bearer token is required, so applications can show what they ask for before the user approves
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rsreddit::client::Reddit;
+    /// use rsreddit::oauth2::RedditApiScope;
+    /// use rsreddit::scope::ScopeSet;
+    /// let reddit = Reddit::default().build();
+    /// let scopes = ScopeSet::from(vec![RedditApiScope::identity, RedditApiScope::read]);
+    /// for scope in reddit.scope_descriptions(Some(&scopes)).unwrap() {
+    ///     println!("{}: {}", scope.name, scope.description);
+    /// }
+    /// ```
+    pub fn scope_descriptions(
+        &self,
+        scopes: Option<&ScopeSet>,
+    ) -> Result<Vec<ScopeDescription>, RedditError> {
+        let answer = match self.read_token()? {
+            Some(token) => {
+                self.send_authorized(self.scope_descriptions_request(scopes, true), &token)?
+            }
+            None => self.send(self.scope_descriptions_request(scopes, false))?,
+        };
+        parse_scope_descriptions(&answer.text())
+    }
+
+    /// `/api/v1/scopes` request. Sent to `oauth_prefix` if `authorized`, to `basic_prefix` otherwise
+    pub(crate) fn scope_descriptions_request(
+        &self,
+        scopes: Option<&ScopeSet>,
+        authorized: bool,
+    ) -> HttpRequest {
+        let query = match scopes {
+            // `*` is not part of the catalogue, all of it is requested instead
+            Some(scopes) if !scopes.contains(RedditApiScope::all) => {
+                let mut params: BTreeMap<&str, String> = BTreeMap::new();
+                params.insert("scopes", scopes.to_string());
+                format!("?{}", convert_map_to_string(&params))
+            }
+            _ => "".to_owned(),
+        };
+        let prefix = if authorized {
+            &self.oauth_prefix
+        } else {
+            &self.basic_prefix
+        };
+        HttpRequest::get(&format!("{}/api/v1/scopes{}", prefix, query))
+    }
+
+    /// Validates the bearer token against reddit and reports what it grants.
+    /// An expired or rejected token is refreshed first, if it has a `refresh_token`
+    ///
+    /// # Returns
+    /// `RedditError::MissingToken` if no bearer token is set,
+    /// `RedditError::HttpStatus` with status `401` if reddit does not accept the token
+    pub fn token_info(&self) -> Result<TokenInfo, RedditError> {
+        let token = self.valid_token()?.ok_or(RedditError::MissingToken)?;
+        // Any authorized request validates the token. `/api/v1/me` additionally names its account
+        let username = if token.has_scope(RedditApiScope::identity) {
+            Some(self.me()?.name)
+        } else {
+            let url = format!("{}/api/v1/scopes", self.oauth_prefix);
+            self.send_authorized(HttpRequest::get(&url), &token)?;
+            None
+        };
+        // The request may have refreshed the token
+        let token = self.current_token().ok_or(RedditError::MissingToken)?;
+        Ok(TokenInfo {
+            username,
+            scopes: token.scopes(),
+            expires_at: token.expires_at(),
+        })
+    }
+
+    //
+    // `identity` SCOPE
+    //
+
+    /// Account the bearer token acts for ( requires `identity` scope )
+    pub fn me(&self) -> Result<Account, RedditError> {
+        let token = self.token_with_scope(RedditApiScope::identity)?;
+        let url = format!("{}/api/v1/me", self.oauth_prefix);
+        let answer = self.send_authorized(HttpRequest::get(&url), &token)?.text();
+        parse_json(&answer)
+    }
+
+    //
+    // `submit` SCOPE
+    //
+
+    /// Submit a new comment or reply to a message
+    /// # Arguments
+    /// * `parent` fullname of parent thing being replied to. Can be fullname of a link, a comment or a message ( requires `submit` scope ).
+    /// * `text` Raw markdown text to comment
+    ///
+    /// # Returns
+    /// `RedditError::InvalidArgument` if `parent` is of another kind
+    pub fn comment(&self, parent: &Fullname, text: &str) -> Result<(), RedditError> {
+        let request = self.comment_request(parent, text)?;
+        let token = self.token_with_scope(RedditApiScope::submit)?;
+        let answer = self.send_authorized(request, &token)?.text();
+        parse_comment_response(&answer)
+    }
+
+    /// `/api/comment` request. Fails if `parent` is no link, comment or message
+    pub(crate) fn comment_request(
+        &self,
+        parent: &Fullname,
+        text: &str,
+    ) -> Result<HttpRequest, RedditError> {
+        parent.expect_kind(&[ThingKind::LINK, ThingKind::COMMENT, ThingKind::MESSAGE])?;
+        let endpoint = "/api/comment";
+        let url = format!("{}{}", self.oauth_prefix, endpoint);
+        let mut payload_map: BTreeMap<&str, String> = BTreeMap::new();
+        payload_map.insert("api_type", "json".to_owned());
+        payload_map.insert("text", text.to_owned());
+        payload_map.insert("return_rtjson", "true".to_owned());
+        payload_map.insert("thing_id", parent.to_string());
+        let payload_data = convert_map_to_string(&payload_map);
+        Ok(HttpRequest::post(&url, &payload_data))
+    }
+
     //
     // `read` SCOPE
     //
@@ -185,17 +575,17 @@ impl Reddit {
     /// * `sr_detail` - expand subreddits
     ///
     /// # Returns
-    /// `Result<Listing, String>` Either Listing of posts or Error message
+    /// `Result<Listing, RedditError>` Either Listing of posts or Error
     pub fn best(
         &self,
         subreddit: Option<&str>,
-        after: &str,
-        before: &str,
+        after: Option<&Fullname>,
+        before: Option<&Fullname>,
         count: u32,
         limit: u32,
         show: bool,
         sr_detail: bool,
-    ) -> Result<Listing, String> {
+    ) -> Result<Listing, RedditError> {
         let sorting = "best".to_string();
         self.get_post_by_sorting(
             sorting, subreddit, None, after, before, count, limit, show, sr_detail,
@@ -217,17 +607,17 @@ impl Reddit {
     /// * `sr_detail` - expand subreddits
     ///
     /// # Returns
-    /// `Result<Listing, String>` Either Listing of posts or Error message
+    /// `Result<Listing, RedditError>` Either Listing of posts or Error
     pub fn hot(
         &self,
         subreddit: Option<&str>,
-        after: &str,
-        before: &str,
+        after: Option<&Fullname>,
+        before: Option<&Fullname>,
         count: u32,
         limit: u32,
         show: bool,
         sr_detail: bool,
-    ) -> Result<Listing, String> {
+    ) -> Result<Listing, RedditError> {
         let sorting = "hot".to_string();
         self.get_post_by_sorting(
             sorting, subreddit, None, after, before, count, limit, show, sr_detail,
@@ -249,17 +639,17 @@ impl Reddit {
     /// * `sr_detail` - expand subreddits
     ///
     /// # Returns
-    /// `Result<Listing, String>` Either Listing of posts or Error message
+    /// `Result<Listing, RedditError>` Either Listing of posts or Error
     pub fn rising(
         &self,
         subreddit: Option<&str>,
-        after: &str,
-        before: &str,
+        after: Option<&Fullname>,
+        before: Option<&Fullname>,
         count: u32,
         limit: u32,
         show: bool,
         sr_detail: bool,
-    ) -> Result<Listing, String> {
+    ) -> Result<Listing, RedditError> {
         let sorting = "rising".to_string();
         self.get_post_by_sorting(
             sorting, subreddit, None, after, before, count, limit, show, sr_detail,
@@ -281,17 +671,18 @@ impl Reddit {
     /// * `sr_detail` - expand subreddits
     ///
     /// # Returns
-    /// `Result<Listing, String>` Either Listing of posts or Error message
+    /// `Result<Listing, RedditError>` Either Listing of posts or Error
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(
         &self,
         subreddit: Option<&str>,
-        after: &str,
-        before: &str,
+        after: Option<&Fullname>,
+        before: Option<&Fullname>,
         count: u32,
         limit: u32,
         show: bool,
         sr_detail: bool,
-    ) -> Result<Listing, String> {
+    ) -> Result<Listing, RedditError> {
         let sorting = "new".to_string();
         self.get_post_by_sorting(
             sorting, subreddit, None, after, before, count, limit, show, sr_detail,
@@ -313,18 +704,18 @@ impl Reddit {
     /// * `sr_detail` - expand subreddits
     ///
     /// # Returns
-    /// `Result<Listing, String>` Either Listing of posts or Error message
+    /// `Result<Listing, RedditError>` Either Listing of posts or Error
     pub fn top(
         &self,
         subreddit: Option<&str>,
         t: SortTime,
-        after: &str,
-        before: &str,
+        after: Option<&Fullname>,
+        before: Option<&Fullname>,
         count: u32,
         limit: u32,
         show: bool,
         sr_detail: bool,
-    ) -> Result<Listing, String> {
+    ) -> Result<Listing, RedditError> {
         let sorting = "top".to_string();
         self.get_post_by_sorting(
             sorting,
@@ -354,18 +745,18 @@ impl Reddit {
     /// * `sr_detail` - expand subreddits
     ///
     /// # Returns
-    /// `Result<Listing, String>` Either Listing of posts or Error message
+    /// `Result<Listing, RedditError>` Either Listing of posts or Error
     pub fn controversial(
         &self,
         subreddit: Option<&str>,
         t: SortTime,
-        after: &str,
-        before: &str,
+        after: Option<&Fullname>,
+        before: Option<&Fullname>,
         count: u32,
         limit: u32,
         show: bool,
         sr_detail: bool,
-    ) -> Result<Listing, String> {
+    ) -> Result<Listing, RedditError> {
         let sorting = "controversial".to_string();
         self.get_post_by_sorting(
             sorting,
@@ -385,21 +776,44 @@ impl Reddit {
         sorting: String,
         subreddit: Option<&str>,
         t: Option<SortTime>,
-        after: &str,
-        before: &str,
+        after: Option<&Fullname>,
+        before: Option<&Fullname>,
         count: u32,
         limit: u32,
         show: bool,
         sr_detail: bool,
-    ) -> Result<Listing, String> {
+    ) -> Result<Listing, RedditError> {
+        let request = self.listing_request(
+            &sorting, subreddit, t, after, before, count, limit, show, sr_detail,
+        )?;
+        let token = self.token_with_scope(RedditApiScope::read)?;
+        let answer = self.send_authorized(request, &token)?.text();
+        parse_json(&answer)
+    }
+
+    /// Listing request of posts sorted by `sorting`. Fails if the parameters are out of bounds
+    pub(crate) fn listing_request(
+        &self,
+        sorting: &str,
+        subreddit: Option<&str>,
+        t: Option<SortTime>,
+        after: Option<&Fullname>,
+        before: Option<&Fullname>,
+        count: u32,
+        limit: u32,
+        show: bool,
+        sr_detail: bool,
+    ) -> Result<HttpRequest, RedditError> {
         // Validate parameters
-        if limit > 100 || limit <= 0 {
-            return Err("Limit bounds are [1, 100]".to_owned());
+        if limit > 100 || limit == 0 {
+            return Err(RedditError::InvalidArgument(
+                "Limit bounds are [1, 100]".to_owned(),
+            ));
         }
-        if after != "" && before != "" {
-            return Err(
+        if after.is_some() && before.is_some() {
+            return Err(RedditError::InvalidArgument(
                 "Set `after` XOR `before`. Do not set both to a specific value.".to_owned(),
-            );
+            ));
         }
         // Get SortTime
         let sort_time_filter = if let Some(sort_t) = t {
@@ -408,46 +822,370 @@ impl Reddit {
             "".to_string()
         };
         // Get subreddit to filter top posts from
-        let subreddit_string = if let Some(sub) = subreddit { sub } else { "" };
-        // Check if bearer token is set
-        if let Some(token) = &self.bearer_token {
-            // Check if correct scope is set for token
-            if !token.scope.contains("read") {
-                return Err("Insufficient scope rights. Need scope: `read`.".to_owned());
-            } else {
-                // Request top posts with set parameters
-                // build authorization HashMap
-                let mut params: HashMap<String, String> = HashMap::new();
-                params.insert("t".to_owned(), sort_time_filter);
-                params.insert("limit".to_owned(), limit.to_string());
-                params.insert("before".to_owned(), before.to_owned());
-                params.insert("after".to_owned(), after.to_owned());
-                params.insert("count".to_owned(), count.to_string());
-                params.insert("show".to_owned(), show.to_string());
-                params.insert("sr_detail".to_owned(), sr_detail.to_string());
-                params.insert("raw_json".to_owned(), "1".to_string());
-                let query_string = convert_map_to_string(&params);
-                let url = format!(
-                    "https://www.reddit.com{}/{}/.json?{}",
-                    subreddit_string, sorting, query_string
-                );
-                let data_header = format!(
-                    "Authorization: Basic {}",
-                    self.client_credentials.client_secret
-                );
-                let answer = get(&url, &data_header);
-                let listing: Listing = serde_json::from_str(&answer).unwrap();
-                return Ok(listing);
+        let subreddit_string = subreddit.unwrap_or_default();
+        // Request top posts with set parameters
+        // build authorization parameters
+        let mut params: BTreeMap<&str, String> = BTreeMap::new();
+        params.insert("t", sort_time_filter);
+        params.insert("limit", limit.to_string());
+        params.insert(
+            "before",
+            before.map(Fullname::to_string).unwrap_or_default(),
+        );
+        params.insert("after", after.map(Fullname::to_string).unwrap_or_default());
+        params.insert("count", count.to_string());
+        params.insert("show", show.to_string());
+        params.insert("sr_detail", sr_detail.to_string());
+        params.insert("raw_json", "1".to_string());
+        let query_string = convert_map_to_string(&params);
+        let url = format!(
+            "{}{}/{}?{}",
+            self.oauth_prefix, subreddit_string, sorting, query_string
+        );
+        Ok(HttpRequest::get(&url))
+    }
+
+    /// Bearer token of the client, if it was granted `scope`
+    fn token_with_scope(&self, scope: RedditApiScope) -> Result<OAuthToken, RedditError> {
+        let token = self.valid_token()?.ok_or(RedditError::MissingToken)?;
+        if !token.has_scope(scope) {
+            return Err(RedditError::MissingScope(scope.to_string()));
+        }
+        Ok(token)
+    }
+
+    /// Bearer token of the client, if it was granted `read` scope. Refreshed first, if it expired.
+    /// Endpoints readable without a token fall back to `basic_prefix` otherwise,
+    /// also if the token expired and cannot be refreshed, like application-only tokens
+    fn read_token(&self) -> Result<Option<OAuthToken>, RedditError> {
+        match self.current_token() {
+            Some(token) if token.is_expired() && !token.is_refreshable() => Ok(None),
+            Some(token) if token.has_scope(RedditApiScope::read) => self.valid_token(),
+            _ => Ok(None),
+        }
+    }
+
+    /// Bearer token of the client. Refreshed first, if it expired.
+    /// An expired token without refresh token is an error instead of a request bound to fail
+    fn valid_token(&self) -> Result<Option<OAuthToken>, RedditError> {
+        let token = self.current_token();
+        match token {
+            Some(token) if token.is_expired() && token.is_refreshable() => {
+                self.refresh(&token).map(Some)
             }
-        } else {
-            return Err("Bearer Token not set. Authorization necessary for this action".to_owned());
+            Some(token) if token.is_expired() => Err(RedditError::Authorization(
+                "Bearer token expired and has no `refresh_token`".to_owned(),
+            )),
+            token => Ok(token),
+        }
+    }
+
+    /// Requests a new access token for `stale` and notifies `on_token_refresh`.
+    /// If another clone of the client refreshed it in the meantime, that token is used instead
+    fn refresh(&self, stale: &OAuthToken) -> Result<OAuthToken, RedditError> {
+        // Only one refresh at a time. The bearer token stays readable while it is on its way
+        let _refreshing = self.refresh_lock.lock();
+        if let Some(token) = self.already_refreshed(stale)? {
+            return Ok(token);
+        }
+        debug!("Refreshing bearer token");
+        let request = refresh_request(&self.client_credentials, stale);
+        let token = parse_refresh_response(self.send(request)?, stale)?;
+        self.install_token(&token);
+        Ok(token)
+    }
+
+    /// Token refreshed by another clone of the client since `stale` was read, if there is one.
+    /// Fails if `stale` has to be refreshed, but has no `refresh_token`
+    pub(crate) fn already_refreshed(
+        &self,
+        stale: &OAuthToken,
+    ) -> Result<Option<OAuthToken>, RedditError> {
+        if let Some(token) = self.current_token() {
+            if token.access_token != stale.access_token {
+                return Ok(Some(token));
+            }
+        }
+        if !stale.is_refreshable() {
+            return Err(RedditError::Authorization(
+                "Token not refreshable `refresh_token` is empty".to_owned(),
+            ));
+        }
+        Ok(None)
+    }
+
+    /// Replaces the bearer token with the refreshed `token`, saves it and notifies `on_token_refresh`
+    pub(crate) fn install_token(&self, token: &OAuthToken) {
+        *self.bearer_token.lock().unwrap() = Some(token.clone());
+        if let Some(store) = &self.token_store {
+            if let Err(e) = store.save(&self.client_credentials.client_id, &self.account, token) {
+                warn!("Could not save refreshed bearer token: {}", e);
+            }
+        }
+        if let Some(callback) = &self.on_token_refresh {
+            (callback.0)(token);
         }
     }
+
+    /// Sends `request` authenticated with `token`.
+    /// If reddit rejects the token, it is refreshed and the request is sent once more
+    fn send_authorized(
+        &self,
+        request: HttpRequest,
+        token: &OAuthToken,
+    ) -> Result<HttpResponse, RedditError> {
+        match self.send(request.clone().header(&bearer_header(token))) {
+            Err(RedditError::HttpStatus { status: 401, .. }) if token.is_refreshable() => {
+                let token = self.refresh(token)?;
+                self.send(request.header(&bearer_header(&token)))
+            }
+            result => result,
+        }
+    }
+
+    /// Sends `request` through the transport, respecting the rate limit and retry policy
+    fn send(&self, request: HttpRequest) -> Result<HttpResponse, RedditError> {
+        let request = self.with_user_agent(request);
+        self.rate_limiter.acquire()?;
+        let result = self.transport.send(&request);
+        self.finish(&request, result)
+    }
+
+    /// Sends `requests` as one batch through the transport. Failed requests are retried one by one
+    fn send_all(&self, requests: Vec<HttpRequest>) -> Vec<Result<HttpResponse, RedditError>> {
+        let requests: Vec<HttpRequest> = requests
+            .into_iter()
+            .map(|r| self.with_user_agent(r))
+            .collect();
+        // Requests exceeding the rate limit are not sent at all
+        let mut results: Vec<Option<Result<HttpResponse, RedditError>>> = Vec::new();
+        let mut batch = Vec::new();
+        for request in &requests {
+            match self.rate_limiter.acquire() {
+                Ok(_) => {
+                    results.push(None);
+                    batch.push(request.clone());
+                }
+                Err(e) => results.push(Some(Err(e))),
+            }
+        }
+        let mut batch_results = self.transport.send_all(&batch).into_iter();
+        requests
+            .iter()
+            .zip(results)
+            .map(|(request, result)| match result {
+                Some(result) => result,
+                None => {
+                    let result = batch_results.next().unwrap_or_else(|| {
+                        Err(RedditError::Transport("Missing response".to_owned()))
+                    });
+                    self.finish(request, result)
+                }
+            })
+            .collect()
+    }
+
+    /// Sets the user agent of the client on `request`
+    pub(crate) fn with_user_agent(&self, request: HttpRequest) -> HttpRequest {
+        match &self.user_agent {
+            Some(user_agent) => request.user_agent(&user_agent.to_string()),
+            None => request,
+        }
+    }
+
+    /// Lock held while the bearer token is refreshed, shared by all clones of the client
+    pub(crate) fn refresh_lock(&self) -> &RefreshLock {
+        &self.refresh_lock
+    }
+
+    /// Rate limiter shared by all clones of the client
+    pub(crate) fn rate_limiter(&self) -> &RateLimiter {
+        &self.rate_limiter
+    }
+
+    /// Updates rate limit with the outcome of the first attempt of `request`
+    /// and sends it again as long as the retry policy allows
+    fn finish(
+        &self,
+        request: &HttpRequest,
+        mut result: Result<HttpResponse, RedditError>,
+    ) -> Result<HttpResponse, RedditError> {
+        let mut attempt = 1;
+        loop {
+            if let Ok(response) = &result {
+                self.rate_limiter.update(response);
+            }
+            if !self.retry_policy.should_retry(request, attempt, &result) {
+                return result?.error_for_status();
+            }
+            let delay = self.retry_policy.delay(attempt, result.as_ref().ok());
+            debug!(
+                "Attempt {} of {} failed. Retrying in {:?}",
+                attempt, request.url, delay
+            );
+            thread::sleep(delay);
+            attempt += 1;
+            self.rate_limiter.acquire()?;
+            result = self.transport.send(request);
+        }
+    }
+}
+
+/// Lock around refreshing the bearer token.
+/// Unlike a `Mutex` guard, its guard may be held across an `.await`, so async and blocking clients can share it
+#[derive(Debug, Default)]
+pub(crate) struct RefreshLock {
+    refreshing: Mutex<bool>,
+    released: Condvar,
+}
+
+impl RefreshLock {
+    /// Blocks until no other refresh is running
+    pub(crate) fn lock(&self) -> RefreshGuard<'_> {
+        let mut refreshing = self.refreshing.lock().unwrap();
+        while *refreshing {
+            refreshing = self.released.wait(refreshing).unwrap();
+        }
+        *refreshing = true;
+        RefreshGuard(self)
+    }
+
+    /// Takes the lock if no other refresh is running
+    pub(crate) fn try_lock(&self) -> Option<RefreshGuard<'_>> {
+        let mut refreshing = self.refreshing.lock().unwrap();
+        if *refreshing {
+            return None;
+        }
+        *refreshing = true;
+        Some(RefreshGuard(self))
+    }
+}
+
+/// Releases the `RefreshLock` when dropped
+pub(crate) struct RefreshGuard<'a>(&'a RefreshLock);
+
+impl Drop for RefreshGuard<'_> {
+    fn drop(&mut self) {
+        *self.0.refreshing.lock().unwrap() = false;
+        self.0.released.notify_all();
+    }
+}
+
+/// Function called with a refreshed bearer token
+#[derive(Clone)]
+struct RefreshCallback(Arc<dyn Fn(&OAuthToken) + Send + Sync>);
+
+impl fmt::Debug for RefreshCallback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RefreshCallback")
+    }
+}
+
+/// (De)serializes the shared bearer token as plain `Option<OAuthToken>`
+mod shared_token {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        token: &Arc<Mutex<Option<OAuthToken>>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        token.lock().unwrap().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Arc<Mutex<Option<OAuthToken>>>, D::Error> {
+        Option::<OAuthToken>::deserialize(deserializer).map(|token| Arc::new(Mutex::new(token)))
+    }
+}
+
+/// `Authorization` header authenticating requests to `oauth_prefix` with `token`
+pub(crate) fn bearer_header(token: &OAuthToken) -> String {
+    format!("Authorization: bearer {}", token.access_token)
+}
+
+/// Parses the answer of a thread request, which is a JSON array of the link and comment listings
+pub(crate) fn parse_thread(answer: &str) -> Result<Thread, RedditError> {
+    let augmented_answer = insert_json_classname("listings", answer);
+    let collection: ListingCollection = parse_json(&augmented_answer)?;
+    Thread::try_from(collection)
+}
+
+/// Parses the answer of `/api/morechildren` into the flat list of comments and `more` stubs
+pub(crate) fn parse_more_children(answer: &str) -> Result<Vec<Thing>, RedditError> {
+    let response: MoreChildrenResponse = parse_json(answer)?;
+    if !response.json.error.errors.is_empty() {
+        return Err(RedditError::Api(response.json.error.api_errors()));
+    }
+    Ok(response
+        .json
+        .data
+        .map(|data| data.things)
+        .unwrap_or_default())
+}
+
+/// Parses the scope catalogue of `/api/v1/scopes`, sorted by id
+pub(crate) fn parse_scope_descriptions(answer: &str) -> Result<Vec<ScopeDescription>, RedditError> {
+    let catalogue: BTreeMap<String, ScopeDescription> = parse_json(answer)?;
+    Ok(catalogue.into_values().collect())
+}
+
+/// Parses the answer of `/api/comment`, turning reported errors into `RedditError::Api`
+pub(crate) fn parse_comment_response(answer: &str) -> Result<(), RedditError> {
+    let comment_response: CommentResponse = parse_json(answer)?;
+    if let Some(err) = comment_response.json {
+        if !err.errors.is_empty() {
+            return Err(RedditError::Api(err.api_errors()));
+        }
+    }
+    Ok(())
+}
+
+/// `more` stubs of `comments` which are not in `expanded` yet
+pub(crate) fn unexpanded_stubs(
+    comments: &Listing,
+    expanded: &HashSet<(String, Fullname)>,
+) -> Vec<More> {
+    comments
+        .more_stubs()
+        .into_iter()
+        .filter(|stub| !expanded.contains(&(stub.name.clone(), stub.parent_id)))
+        .cloned()
+        .collect()
+}
+
+/// Permalink of the replies of comment `parent` hidden behind a "continue this thread" stub
+pub(crate) fn continue_thread_permalink(link: &Fullname, parent: &Fullname) -> String {
+    format!("/comments/{}/_/{}", link.id(), parent.id())
+}
+
+/// Replies of `parent` in `thread`, as returned for `continue_thread_permalink`
+pub(crate) fn continued_replies(thread: Thread, parent: &Fullname) -> Vec<Thing> {
+    // The tree of the permalink starts at `parent`
+    let replies = thread
+        .comments
+        .data
+        .children
+        .into_iter()
+        .find_map(|child| match child {
+            Thing::Comment(comment) if comment.name == *parent => comment.replies,
+            _ => None,
+        });
+    replies
+        .map(|replies| replies.data.children)
+        .unwrap_or_default()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_fixtures::{comment_json, thread_json, token};
+    use crate::transport::MockTransport;
+    use std::time::Duration;
+
+    fn link() -> Fullname {
+        "t3_abc".parse().unwrap()
+    }
 
     #[test]
     fn test_build_default_reddit() {
@@ -475,4 +1213,559 @@ mod tests {
     fn test_use_reddit_without_basic_prefix() {
         Reddit::default().basic_prefix("").build();
     }
+
+    #[test]
+    #[should_panic(expected = "No prefixes provided. Cannot communicate with reddit API endpoint!")]
+    fn test_use_reddit_without_bearer_prefix() {
+        Reddit::default().oauth_prefix("").build();
+    }
+
+    #[test]
+    fn test_listing_uses_oauth_prefix_and_bearer_token() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(200, "{}");
+        let reddit = Reddit::default()
+            .transport(mock.clone())
+            .oauth_prefix("https://oauth.example.com")
+            .bearer_token(token("read"))
+            .build();
+        let _ = reddit.hot(Some("/r/rust"), None, None, 0, 25, false, false);
+        let requests = mock.requests();
+        assert!(requests[0]
+            .url
+            .starts_with("https://oauth.example.com/r/rust/hot?"));
+        assert_eq!(requests[0].headers, vec!["Authorization: bearer access"]);
+    }
+
+    #[test]
+    fn test_thread_prefix_depends_on_token() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(200, &thread_json(&[]));
+        mock.push_response(200, &thread_json(&[]));
+        let reddit = Reddit::default().transport(mock.clone()).build();
+        reddit.thread_by_permalink("/r/rust/comments/a/").unwrap();
+        let reddit = reddit.bearer_token(token("read"));
+        reddit.thread_by_permalink("/r/rust/comments/a/").unwrap();
+        let requests = mock.requests();
+        assert_eq!(
+            requests[0].url,
+            "https://www.reddit.com/r/rust/comments/a/.json"
+        );
+        assert!(requests[0].headers.is_empty());
+        assert_eq!(
+            requests[1].url,
+            "https://oauth.reddit.com/r/rust/comments/a/"
+        );
+        assert_eq!(requests[1].headers, vec!["Authorization: bearer access"]);
+    }
+
+    #[test]
+    fn test_more_children_batches() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(200, r#"{"json": {"errors": [], "data": {"things": []}}}"#);
+        mock.push_response(200, r#"{"json": {"errors": [], "data": {"things": []}}}"#);
+        let reddit = Reddit::default().transport(mock.clone()).build();
+        let children: Vec<ThingId> = (0..150).map(ThingId::new).collect();
+        let link: Fullname = "t3_l1".parse().unwrap();
+        reddit.more_children(&link, &children, true).unwrap();
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].url.starts_with(
+            "https://www.reddit.com/api/morechildren.json?api_type=json&children=2s%2C2t%2C"
+        ));
+        assert!(reddit
+            .more_children(&children[0].fullname(ThingKind::COMMENT), &children, true)
+            .is_err());
+    }
+
+    #[test]
+    fn test_more_children_without_read_scope_uses_basic_prefix() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(200, r#"{"json": {"errors": [], "data": {"things": []}}}"#);
+        let reddit = Reddit::default()
+            .transport(mock.clone())
+            .bearer_token(token("submit"))
+            .build();
+        reddit
+            .more_children(&link(), &[ThingId::new(1)], false)
+            .unwrap();
+        let requests = mock.requests();
+        assert!(requests[0]
+            .url
+            .starts_with("https://www.reddit.com/api/morechildren.json?"));
+        assert!(requests[0].headers.is_empty());
+    }
+
+    #[test]
+    fn test_expand_thread() {
+        let more = r#"{"kind": "more", "data": {"id": "c3", "name": "t1_c3", "parent_id": "t1_c1", "count": 2, "depth": 1, "children": ["c3", "c4"]}}"#;
+        let continue_thread = r#"{"kind": "more", "data": {"id": "_", "name": "t1__", "parent_id": "t1_c2", "count": 0, "depth": 1, "children": []}}"#;
+        let thread = thread_json(&[
+            comment_json("c1", "t3_l1", &[more.to_owned()]),
+            comment_json("c2", "t3_l1", &[continue_thread.to_owned()]),
+        ]);
+        let things = [
+            comment_json("c3", "t1_c1", &[]),
+            comment_json("c5", "t1_c3", &[]),
+            comment_json("c4", "t1_c1", &[]),
+        ];
+        let continued = thread_json(&[comment_json(
+            "c2",
+            "t3_l1",
+            &[comment_json("c6", "t1_c2", &[])],
+        )]);
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(200, &thread);
+        mock.push_response(
+            200,
+            &format!(
+                r#"{{"json": {{"errors": [], "data": {{"things": [{}]}}}}}}"#,
+                things.join(",")
+            ),
+        );
+        mock.push_response(200, &continued);
+        let reddit = Reddit::default().transport(mock.clone()).build();
+        let mut tree = reddit.thread_by_permalink("/r/rust/comments/l1/").unwrap();
+        reddit.expand_thread(&mut tree).unwrap();
+        let comments = &tree.comments;
+        assert!(comments.more_stubs().is_empty());
+        let replies = |thing: &Thing| -> Vec<String> {
+            thing
+                .as_comment()
+                .unwrap()
+                .replies
+                .as_ref()
+                .unwrap()
+                .data
+                .children
+                .iter()
+                .map(|c| c.as_comment().unwrap().body.clone())
+                .collect()
+        };
+        let first = &comments.data.children[0];
+        assert_eq!(replies(first), vec!["c3", "c4"]);
+        assert_eq!(
+            replies(
+                &first
+                    .as_comment()
+                    .unwrap()
+                    .replies
+                    .as_ref()
+                    .unwrap()
+                    .data
+                    .children[0]
+            ),
+            vec!["c5"]
+        );
+        assert_eq!(replies(&comments.data.children[1]), vec!["c6"]);
+        let requests = mock.requests();
+        assert!(requests[1]
+            .url
+            .contains("children=c3%2Cc4&limit_children=false&link_id=t3_l1"));
+        assert_eq!(
+            requests[2].url,
+            "https://www.reddit.com/comments/l1/_/c2.json"
+        );
+    }
+
+    #[test]
+    fn test_comment_with_mock_transport() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(200, r#"{"id": "abc"}"#);
+        let reddit = Reddit::default()
+            .transport(mock.clone())
+            .bearer_token(token("submit"))
+            .build();
+        reddit.comment(&link(), "Tom & Jerry\n1+1=2").unwrap();
+        let requests = mock.requests();
+        assert_eq!(requests[0].url, "https://oauth.reddit.com/api/comment");
+        assert_eq!(requests[0].headers, vec!["Authorization: bearer access"]);
+        assert_eq!(
+            requests[0].body,
+            "api_type=json&return_rtjson=true&text=Tom%20%26%20Jerry%0A1%2B1%3D2&thing_id=t3_abc"
+        );
+    }
+
+    #[test]
+    fn test_comment_rejects_wrong_kind() {
+        let mock = Arc::new(MockTransport::default());
+        let reddit = Reddit::default()
+            .transport(mock.clone())
+            .bearer_token(token("submit"))
+            .build();
+        let subreddit: Fullname = "t5_2fwo".parse().unwrap();
+        match reddit.comment(&subreddit, "Hello") {
+            Err(RedditError::InvalidArgument(_)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert!(mock.requests().is_empty());
+    }
+
+    #[test]
+    fn test_comment_api_errors() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(
+            200,
+            r#"{"json": {"errors": [["TOO_LONG", "this is too long", "text"]]}}"#,
+        );
+        let reddit = Reddit::default()
+            .transport(mock)
+            .bearer_token(token("submit"))
+            .build();
+        match reddit.comment(&link(), "Hello") {
+            Err(RedditError::Api(errors)) => assert_eq!(errors[0].code, "TOO_LONG"),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_comment_missing_scope() {
+        let reddit = Reddit::default()
+            .transport(Arc::new(MockTransport::default()))
+            .bearer_token(token("read"))
+            .build();
+        match reddit.comment(&link(), "Hello") {
+            Err(RedditError::MissingScope(scope)) => assert_eq!(scope, "submit"),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_scope_descriptions_without_token() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(
+            200,
+            r#"{"read": {"id": "read", "name": "Read Content", "description": "Access posts and comments through my account."},
+                "identity": {"id": "identity", "name": "My Identity", "description": "Access my reddit username and signup date."}}"#,
+        );
+        // Tokens without `read` scope are not sent
+        let reddit = Reddit::default()
+            .transport(mock.clone())
+            .bearer_token(token("identity"))
+            .build();
+        let scopes = ScopeSet::from(vec![RedditApiScope::read, RedditApiScope::identity]);
+        let descriptions = reddit.scope_descriptions(Some(&scopes)).unwrap();
+        assert_eq!(descriptions[0].id, "identity");
+        assert_eq!(descriptions[1].name, "Read Content");
+        let requests = mock.requests();
+        assert_eq!(
+            requests[0].url,
+            "https://www.reddit.com/api/v1/scopes?scopes=identity%20read"
+        );
+        assert!(requests[0].headers.is_empty());
+    }
+
+    #[test]
+    fn test_token_info() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(
+            200,
+            r#"{"id": "abc", "name": "spez", "created_utc": 1118030400.0, "link_karma": 1, "comment_karma": 2}"#,
+        );
+        mock.push_response(200, "{}");
+        let reddit = Reddit::default()
+            .transport(mock.clone())
+            .bearer_token(token("identity read"))
+            .build();
+        let info = reddit.token_info().unwrap();
+        assert_eq!(info.username, Some("spez".to_owned()));
+        assert!(info.scopes.contains(RedditApiScope::read));
+        let reddit = reddit.bearer_token(token("read"));
+        let info = reddit.token_info().unwrap();
+        assert_eq!(info.username, None);
+        let requests = mock.requests();
+        assert_eq!(requests[0].url, "https://oauth.reddit.com/api/v1/me");
+        assert_eq!(requests[1].url, "https://oauth.reddit.com/api/v1/scopes");
+        assert_eq!(requests[1].headers, vec!["Authorization: bearer access"]);
+    }
+
+    #[test]
+    fn test_token_info_rejected_token() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(401, r#"{"message": "Unauthorized", "error": 401}"#);
+        let reddit = Reddit::default()
+            .transport(mock)
+            .bearer_token(token("read"))
+            .build();
+        match reddit.token_info() {
+            Err(RedditError::HttpStatus { status, .. }) => assert_eq!(status, 401),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_rate_limit_error_mode() {
+        let mock = Arc::new(MockTransport::default());
+        let mut response = HttpResponse::new(404, "{}");
+        response
+            .headers
+            .insert("x-ratelimit-remaining".to_owned(), "0.0".to_owned());
+        response
+            .headers
+            .insert("x-ratelimit-reset".to_owned(), "300".to_owned());
+        mock.push(Ok(response));
+        let reddit = Reddit::default()
+            .transport(mock.clone())
+            .rate_limit_mode(RateLimitMode::ERROR)
+            .build();
+        assert!(reddit.thread_by_permalink("/r/rust/comments/abc/").is_err());
+        match reddit.thread_by_permalink("/r/rust/comments/abc/") {
+            Err(RedditError::RateLimited { .. }) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        // Second request never reached the transport
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[test]
+    fn test_retry_server_errors() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(503, "<html>Maintenance</html>");
+        mock.push(Err(RedditError::Transport("Connection reset".to_owned())));
+        mock.push_response(502, "<html>Bad Gateway</html>");
+        let reddit = Reddit::default()
+            .transport(mock.clone())
+            .retry_policy(RetryPolicy::default().base_delay(Duration::from_millis(1)))
+            .build();
+        match reddit.thread_by_permalink("/r/rust/comments/abc/") {
+            Err(RedditError::HttpStatus { status, .. }) => assert_eq!(status, 502),
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert_eq!(mock.requests().len(), 3);
+    }
+
+    #[test]
+    fn test_no_retry_for_post_by_default() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(503, "<html>Maintenance</html>");
+        let reddit = Reddit::default()
+            .transport(mock.clone())
+            .bearer_token(token("submit"))
+            .build();
+        assert!(reddit.comment(&link(), "Hello").is_err());
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[test]
+    fn test_threads_by_permalinks() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(200, &thread_json(&[]));
+        mock.push_response(404, "{}");
+        let reddit = Reddit::default().transport(mock.clone()).build();
+        let threads = reddit.threads_by_permalinks(&["/r/rust/comments/a/", "/r/rust/comments/b/"]);
+        assert!(threads[0].as_ref().unwrap().is_empty());
+        assert!(threads[1].is_err());
+        assert_eq!(
+            mock.requests()[1].url,
+            "https://www.reddit.com/r/rust/comments/b/.json"
+        );
+    }
+
+    #[test]
+    fn test_thread_without_read_scope_uses_basic_prefix() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(200, &thread_json(&[]));
+        let reddit = Reddit::default()
+            .transport(mock.clone())
+            .bearer_token(token("identity submit"))
+            .build();
+        reddit.thread_by_permalink("/r/rust/comments/a/").unwrap();
+        let requests = mock.requests();
+        assert_eq!(
+            requests[0].url,
+            "https://www.reddit.com/r/rust/comments/a/.json"
+        );
+        assert!(requests[0].headers.is_empty());
+    }
+
+    #[test]
+    fn test_expired_application_token() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(200, &thread_json(&[]));
+        let mut expired = token("*");
+        expired.created_at -= 3600;
+        let reddit = Reddit::default()
+            .transport(mock.clone())
+            .bearer_token(expired)
+            .build();
+        // Readable endpoints fall back to `basic_prefix`
+        reddit.thread_by_permalink("/r/rust/comments/a/").unwrap();
+        let requests = mock.requests();
+        assert_eq!(
+            requests[0].url,
+            "https://www.reddit.com/r/rust/comments/a/.json"
+        );
+        assert!(requests[0].headers.is_empty());
+        match reddit.comment(&link(), "Hello") {
+            Err(RedditError::Authorization(e)) => assert!(e.contains("expired")),
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[test]
+    fn test_threads_by_permalinks_keeps_refresh_error() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(503, "");
+        let mut expired = token("read");
+        expired.refresh_token = "refresh".to_owned();
+        expired.created_at -= 3600;
+        let reddit = Reddit::default()
+            .transport(mock.clone())
+            .bearer_token(expired)
+            .build();
+        let threads = reddit.threads_by_permalinks(&["/r/rust/comments/a/", "/r/rust/comments/b/"]);
+        assert_eq!(threads.len(), 2);
+        for thread in threads {
+            match thread {
+                Err(RedditError::HttpStatus { status, .. }) => assert_eq!(status, 503),
+                other => panic!("Unexpected result {:?}", other),
+            }
+        }
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[test]
+    fn test_user_agent_is_sent() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(200, &thread_json(&[]));
+        let reddit = Reddit::default()
+            .transport(mock.clone())
+            .user_agent(UserAgent::new("linux", "test_app", "v1.0", "tester"))
+            .build();
+        reddit.thread_by_permalink("/r/rust/comments/a/").unwrap();
+        assert_eq!(
+            mock.requests()[0].user_agent,
+            Some("linux:test_app:v1.0 (by /u/tester)".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_expired_token_is_refreshed() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(
+            200,
+            r#"{"access_token": "new", "token_type": "bearer", "expires_in": 3600, "scope": "read"}"#,
+        );
+        mock.push_response(200, &thread_json(&[]));
+        let refreshed = Arc::new(Mutex::new(Vec::new()));
+        let on_refresh = refreshed.clone();
+        let mut expired = token("read");
+        expired.refresh_token = "refresh".to_owned();
+        expired.created_at -= 3600;
+        let reddit = Reddit::default()
+            .transport(mock.clone())
+            .bearer_token(expired)
+            .on_token_refresh(move |token| {
+                on_refresh.lock().unwrap().push(token.access_token.clone())
+            })
+            .build();
+        reddit.thread_by_permalink("/r/rust/comments/a/").unwrap();
+        let requests = mock.requests();
+        assert_eq!(
+            requests[0].url,
+            "https://www.reddit.com/api/v1/access_token"
+        );
+        assert_eq!(requests[1].headers, vec!["Authorization: bearer new"]);
+        assert_eq!(*refreshed.lock().unwrap(), vec!["new"]);
+        let current = reddit.current_token().unwrap();
+        assert_eq!(current.refresh_token, "refresh");
+    }
+
+    /// Holds back the first request until it is released
+    #[derive(Debug)]
+    struct GatedTransport {
+        inner: MockTransport,
+        entered: Mutex<Option<std::sync::mpsc::Sender<()>>>,
+        release: Mutex<std::sync::mpsc::Receiver<()>>,
+    }
+
+    impl HttpTransport for GatedTransport {
+        fn send(&self, request: &HttpRequest) -> Result<HttpResponse, RedditError> {
+            if let Some(entered) = self.entered.lock().unwrap().take() {
+                entered.send(()).unwrap();
+                self.release.lock().unwrap().recv().unwrap();
+            }
+            self.inner.send(request)
+        }
+    }
+
+    #[test]
+    fn test_token_readable_during_refresh() {
+        let (entered_tx, entered_rx) = std::sync::mpsc::channel();
+        let (release_tx, release_rx) = std::sync::mpsc::channel();
+        let transport = GatedTransport {
+            inner: MockTransport::default(),
+            entered: Mutex::new(Some(entered_tx)),
+            release: Mutex::new(release_rx),
+        };
+        transport.inner.push_response(
+            200,
+            r#"{"access_token": "new", "token_type": "bearer", "expires_in": 3600, "scope": "read"}"#,
+        );
+        transport.inner.push_response(200, &thread_json(&[]));
+        let mut expired = token("read");
+        expired.refresh_token = "refresh".to_owned();
+        expired.created_at -= 3600;
+        let reddit = Reddit::default()
+            .transport(Arc::new(transport))
+            .bearer_token(expired)
+            .build();
+        let refreshing = {
+            let reddit = reddit.clone();
+            thread::spawn(move || reddit.thread_by_permalink("/r/rust/comments/a/"))
+        };
+        entered_rx.recv().unwrap();
+        assert_eq!(reddit.current_token().unwrap().access_token, "access");
+        release_tx.send(()).unwrap();
+        refreshing.join().unwrap().unwrap();
+        assert_eq!(reddit.current_token().unwrap().access_token, "new");
+    }
+
+    #[test]
+    fn test_rejected_token_is_refreshed_and_request_retried() {
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(401, "{}");
+        mock.push_response(
+            200,
+            r#"{"access_token": "new", "token_type": "bearer", "expires_in": 3600, "scope": "submit"}"#,
+        );
+        mock.push_response(200, r#"{"id": "abc"}"#);
+        let mut rejected = token("submit");
+        rejected.refresh_token = "refresh".to_owned();
+        let reddit = Reddit::default()
+            .transport(mock.clone())
+            .bearer_token(rejected)
+            .build();
+        reddit.comment(&link(), "Hello").unwrap();
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[2].headers, vec!["Authorization: bearer new"]);
+    }
+
+    #[test]
+    fn test_token_store() {
+        let store = Arc::new(crate::token_store::MemoryTokenStore::default());
+        let credentials = RedditClientCredentials::default().client_id("app");
+        let mut stored = token("read");
+        stored.refresh_token = "refresh".to_owned();
+        stored.created_at -= 3600;
+        store.save("app", "alice", &stored).unwrap();
+        let mock = Arc::new(MockTransport::default());
+        mock.push_response(
+            200,
+            r#"{"access_token": "new", "token_type": "bearer", "expires_in": 3600, "scope": "read"}"#,
+        );
+        mock.push_response(200, &thread_json(&[]));
+        let reddit = Reddit::default()
+            .transport(mock)
+            .client_credentials(&credentials)
+            .token_store(store.clone())
+            .account("alice")
+            .build();
+        reddit.thread_by_permalink("/r/rust/comments/a/").unwrap();
+        let saved = store.load("app", "alice").unwrap().unwrap();
+        assert_eq!(saved.access_token, "new");
+        assert_eq!(saved.refresh_token, "refresh");
+    }
 }
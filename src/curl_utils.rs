@@ -1,86 +1,215 @@
 //Built in libraries
-use std::io::Read;
+use std::collections::HashMap;
+use std::time::Duration;
 
 //Third party libraries
-use curl::easy::{Easy, List};
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use curl::easy::{Easy2, Handler, List, WriteError};
+use curl::multi::Multi;
 
 //Own stuff
-use super::model::token::OAuthToken;
-use super::VERSION;
+use super::error::RedditError;
+use super::transport::{HttpMethod, HttpRequest, HttpResponse};
+use super::user_agent::default_user_agent;
 
-/// POST Curl request
+/// Collects headers and raw body of a response
+#[derive(Debug, Default)]
+pub struct Collector {
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+impl Handler for Collector {
+    fn write(&mut self, data: &[u8]) -> Result<usize, WriteError> {
+        // Collect raw bytes. Decoding happens once the body is complete,
+        // as a UTF-8 sequence may be split over two callbacks
+        self.body.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn header(&mut self, data: &[u8]) -> bool {
+        parse_header_line(&mut self.headers, data);
+        true
+    }
+}
+
+/// Curl handle. Keeps its connections open between requests, as long as it is not dropped
+pub type Handle = Easy2<Collector>;
+
+/// Creates new curl handle
+pub fn new_handle() -> Handle {
+    Easy2::new(Collector::default())
+}
+
+/// Curl request reusing `handle`
 /// # Arguments
 ///
-/// * `complete_url` - url for curl request
-/// * `payload` - payload for post request, if request uses POST
-/// * `header` - header data
-pub fn post(complete_url: &str, payload: &str, header: &str) -> String {
-    let user_agent_header = format!("User-Agent: rsreddit:v{} (by /u/Gitrog_Frog)", VERSION);
-    let mut easy = Easy::new();
+/// * `handle` - curl handle, which may have been used for previous requests
+/// * `request` - url, headers and payload of the request
+pub fn perform(handle: &mut Handle, request: &HttpRequest) -> Result<HttpResponse, RedditError> {
+    prepare(handle, request)?;
+    handle.perform()?;
+    take_response(handle)
+}
 
-    easy.url(&complete_url).unwrap();
-    easy.useragent(&user_agent_header).unwrap();
+/// Performs `requests` in parallel with curl's multi interface.
+/// Results are in the same order as `requests`. Handles are handed back afterwards to be reused
+/// # Arguments
+///
+/// * `handles` - curl handles, one for each request
+/// * `requests` - url, headers and payload of the requests
+pub fn perform_all(
+    handles: Vec<Handle>,
+    requests: &[HttpRequest],
+) -> (Vec<Handle>, Vec<Result<HttpResponse, RedditError>>) {
+    let multi = Multi::new();
+    let mut results: Vec<Option<Result<HttpResponse, RedditError>>> =
+        requests.iter().map(|_| None).collect();
+    let mut returned = Vec::new();
+    let mut added = Vec::new();
+    for (index, (mut handle, request)) in handles.into_iter().zip(requests).enumerate() {
+        if let Err(e) = prepare(&mut handle, request) {
+            results[index] = Some(Err(e));
+            returned.push(handle);
+            continue;
+        }
+        match multi.add2(handle) {
+            Ok(easy_handle) => added.push((index, easy_handle)),
+            Err(e) => results[index] = Some(Err(RedditError::from(e))),
+        }
+    }
+    // Drive transfers until all of them are done
+    loop {
+        match multi.perform() {
+            Ok(0) => break,
+            Ok(_) => {
+                if let Err(e) = multi.wait(&mut [], Duration::from_secs(1)) {
+                    warn!("Waiting for curl transfers failed: {}", e);
+                }
+            }
+            Err(e) => {
+                warn!("Performing curl transfers failed: {}", e);
+                break;
+            }
+        }
+    }
+    // Collect transfer errors
+    multi.messages(|message| {
+        for (index, easy_handle) in &added {
+            if let Some(Err(e)) = message.result_for2(easy_handle) {
+                results[*index] = Some(Err(RedditError::from(e)));
+            }
+        }
+    });
+    for (index, easy_handle) in added {
+        match multi.remove2(easy_handle) {
+            Ok(mut handle) => {
+                if results[index].is_none() {
+                    results[index] = Some(take_response(&mut handle));
+                }
+                returned.push(handle);
+            }
+            Err(e) => results[index] = Some(Err(RedditError::from(e))),
+        }
+    }
+    let results = results
+        .into_iter()
+        .map(|r| {
+            r.unwrap_or_else(|| Err(RedditError::Transport("Transfer did not finish".to_owned())))
+        })
+        .collect();
+    (returned, results)
+}
+
+/// Resets `handle` and sets url, user agent, headers and payload of `request`.
+/// Resetting keeps open connections, so the next request to the same host skips the TLS handshake
+pub fn prepare(handle: &mut Handle, request: &HttpRequest) -> Result<(), RedditError> {
+    let user_agent = request
+        .user_agent
+        .clone()
+        .unwrap_or_else(default_user_agent);
+    handle.reset();
+    *handle.get_mut() = Collector::default();
+
+    handle.url(&request.url)?;
+    handle.useragent(&user_agent)?;
 
     // Set Header
     let mut list = List::new();
-    list.append(header).unwrap();
-    easy.http_headers(list).unwrap();
+    for header in &request.headers {
+        list.append(header)?;
+    }
+    handle.http_headers(list)?;
 
     // Set post payload
-    let mut data_field = payload.as_bytes();
-    easy.post(true).unwrap();
-    easy.post_field_size(data_field.len() as u64).unwrap();
-
-    let mut return_data: Vec<String> = Vec::new();
-    let mut html: String = String::new();
-    {
-        let mut transfer = easy.transfer();
-        transfer
-            .read_function(|buf| Ok(data_field.read(buf).unwrap_or(0)))
-            .unwrap();
-        transfer
-            .write_function(|data| {
-                html = String::from_utf8(Vec::from(data)).unwrap();
-                return_data.push(html.clone());
-                Ok(data.len())
-            })
-            .unwrap();
-        transfer.perform().unwrap();
-    };
-    return_data.join("")
+    if request.method == HttpMethod::POST {
+        handle.post(true)?;
+        handle.post_fields_copy(request.body.as_bytes())?;
+    }
+    Ok(())
 }
 
-/// GET Curl request
-/// # Arguments
-///
-/// * `complete_url` - url for curl request
-/// * `header` - header data
-pub fn get(complete_url: &str, header: &str) -> String {
-    let user_agent_header = format!("User-Agent: rsreddit:v{} (by /u/Gitrog_Frog)", VERSION);
-    let mut easy = Easy::new();
+/// Moves status, headers and body of the last response out of `handle`
+pub fn take_response(handle: &mut Handle) -> Result<HttpResponse, RedditError> {
+    let status = handle.response_code()?;
+    let collector = std::mem::take(handle.get_mut());
+    Ok(HttpResponse {
+        status,
+        headers: collector.headers,
+        body: collector.body,
+    })
+}
 
-    easy.url(&complete_url).unwrap();
-    easy.useragent(&user_agent_header).unwrap();
+/// Adds one raw header line to `headers`.
+/// A status line (`HTTP/1.1 200 OK`) starts a new header block, e.g. after a redirect,
+/// so headers of previous responses are discarded
+fn parse_header_line(headers: &mut HashMap<String, String>, line: &[u8]) {
+    let line = String::from_utf8_lossy(line);
+    if line.starts_with("HTTP/") {
+        headers.clear();
+        return;
+    }
+    if let Some(index) = line.find(':') {
+        let name = line[..index].trim().to_lowercase();
+        let value = line[index + 1..].trim().to_owned();
+        headers.insert(name, value);
+    }
+}
 
-    // Set Header
-    let mut list = List::new();
-    list.append(header).unwrap();
-    easy.http_headers(list).unwrap();
-
-    let mut return_data: Vec<String> = Vec::new();
-    let mut html: String = String::new();
-    {
-        let mut transfer = easy.transfer();
-        transfer
-            .write_function(|data| {
-                html = String::from_utf8(Vec::from(data)).unwrap();
-                return_data.push(html.clone());
-                Ok(data.len())
-            })
-            .unwrap();
-        transfer.perform().unwrap();
-    };
-    return_data.join("")
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_header_line() {
+        let mut headers = HashMap::new();
+        parse_header_line(&mut headers, b"HTTP/1.1 200 OK\r\n");
+        parse_header_line(&mut headers, b"X-Ratelimit-Remaining: 599.0\r\n");
+        parse_header_line(&mut headers, b"\r\n");
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers["x-ratelimit-remaining"], "599.0");
+    }
+
+    #[test]
+    fn test_parse_header_line_resets_on_new_status_line() {
+        let mut headers = HashMap::new();
+        parse_header_line(&mut headers, b"HTTP/1.1 301 Moved Permanently\r\n");
+        parse_header_line(&mut headers, b"Location: https://www.reddit.com/\r\n");
+        parse_header_line(&mut headers, b"HTTP/1.1 200 OK\r\n");
+        assert!(headers.is_empty());
+    }
+
+    #[test]
+    fn test_collector_joins_split_utf8() {
+        let mut collector = Collector::default();
+        let bytes = "Grüße".as_bytes();
+        // Multi byte `ü` arrives split over two write callbacks
+        assert_eq!(collector.write(&bytes[..3]).ok(), Some(3));
+        assert_eq!(collector.write(&bytes[3..]).ok(), Some(bytes.len() - 3));
+        let response = HttpResponse {
+            status: 200,
+            headers: collector.headers,
+            body: collector.body,
+        };
+        assert_eq!(response.text(), "Grüße");
+    }
 }
@@ -0,0 +1,238 @@
+//! Error type shared by every client and OAuth2 call
+
+//Third party libraries
+use failure::Fail;
+use serde_json::Value;
+
+/// Errors returned by the reddit API client and the OAuth2 flow
+#[derive(Clone)]
+pub enum RedditError {
+    /// Request could not be sent or the connection broke down
+    Transport(String),
+    /// Reddit answered with a non-success HTTP status code. Raw body is attached
+    HttpStatus { status: u32, body: String },
+    /// Reddit accepted the request but reported errors in `json.errors`
+    Api(Vec<ApiError>),
+    /// Bearer token lacks a scope necessary for the endpoint
+    MissingScope(String),
+    /// Endpoint needs a bearer token, but none is set
+    MissingToken,
+    /// Response body could not be deserialized. Raw body is attached
+    Deserialize { error: String, body: String },
+    /// Parameters passed to an endpoint are invalid
+    InvalidArgument(String),
+    /// Authorization of the user failed
+    Authorization(String),
+    /// Request quota is used up. Retry after `retry_after` seconds
+    RateLimited { retry_after: u64 },
+    /// Token could not be loaded from or written to a `TokenStore`
+    TokenStore(String),
+    /// Authorization dialog did not end with a usable code
+    OAuth(OAuthError),
+    /// Response deserialized, but lacks parts the endpoint always returns
+    UnexpectedResponse(String),
+}
+
+impl Fail for RedditError {}
+
+impl std::fmt::Display for RedditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RedditError::Transport(e) => write!(f, "Transport error: {}", e),
+            RedditError::HttpStatus { status, .. } => {
+                write!(f, "Reddit answered with HTTP status {}", status)
+            }
+            RedditError::Api(errors) => {
+                let errors: Vec<String> = errors.iter().map(ApiError::to_string).collect();
+                write!(f, "Reddit API returned errors: {}", errors.join(", "))
+            }
+            RedditError::MissingScope(scope) => {
+                write!(f, "Insufficient scope rights. Need scope: `{}`.", scope)
+            }
+            RedditError::MissingToken => write!(
+                f,
+                "Bearer Token not set. Authorization necessary for this action"
+            ),
+            RedditError::Deserialize { error, .. } => {
+                write!(f, "Could not deserialize response: {}", error)
+            }
+            RedditError::InvalidArgument(e) => write!(f, "Invalid argument: {}", e),
+            RedditError::Authorization(e) => write!(f, "Authorization failed: {}", e),
+            RedditError::RateLimited { retry_after } => {
+                write!(f, "Rate limit reached. Retry after {} seconds", retry_after)
+            }
+            RedditError::TokenStore(e) => write!(f, "Token store error: {}", e),
+            RedditError::OAuth(e) => write!(f, "Authorization failed: {}", e),
+            RedditError::UnexpectedResponse(e) => write!(f, "Unexpected response: {}", e),
+        }
+    }
+}
+
+impl std::fmt::Debug for RedditError {
+    /// Raw bodies are left out, as answers of the access token endpoint contain tokens
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RedditError::Transport(e) => f.debug_tuple("Transport").field(e).finish(),
+            RedditError::HttpStatus { status, body } => f
+                .debug_struct("HttpStatus")
+                .field("status", status)
+                .field("body", &format_args!("<{} bytes>", body.len()))
+                .finish(),
+            RedditError::Api(errors) => f.debug_tuple("Api").field(errors).finish(),
+            RedditError::MissingScope(scope) => f.debug_tuple("MissingScope").field(scope).finish(),
+            RedditError::MissingToken => write!(f, "MissingToken"),
+            RedditError::Deserialize { error, body } => f
+                .debug_struct("Deserialize")
+                .field("error", error)
+                .field("body", &format_args!("<{} bytes>", body.len()))
+                .finish(),
+            RedditError::InvalidArgument(e) => f.debug_tuple("InvalidArgument").field(e).finish(),
+            RedditError::Authorization(e) => f.debug_tuple("Authorization").field(e).finish(),
+            RedditError::RateLimited { retry_after } => f
+                .debug_struct("RateLimited")
+                .field("retry_after", retry_after)
+                .finish(),
+            RedditError::TokenStore(e) => f.debug_tuple("TokenStore").field(e).finish(),
+            RedditError::OAuth(e) => f.debug_tuple("OAuth").field(e).finish(),
+            RedditError::UnexpectedResponse(e) => {
+                f.debug_tuple("UnexpectedResponse").field(e).finish()
+            }
+        }
+    }
+}
+
+impl From<curl::Error> for RedditError {
+    fn from(error: curl::Error) -> RedditError {
+        RedditError::Transport(error.to_string())
+    }
+}
+
+impl From<curl::MultiError> for RedditError {
+    fn from(error: curl::MultiError) -> RedditError {
+        RedditError::Transport(error.to_string())
+    }
+}
+
+/// Outcome of the authorization dialog other than a code.
+/// See https://github.com/reddit-archive/reddit/wiki/OAuth2#token-retrieval-code-flow
+#[derive(Clone, Debug, PartialEq)]
+pub enum OAuthError {
+    /// User declined to authorize the app
+    AccessDenied,
+    /// `response_type` of the authorization URL is invalid
+    UnsupportedResponseType,
+    /// `scope` of the authorization URL is invalid
+    InvalidScope,
+    /// Authorization URL is malformed, e.g. a parameter is missing
+    InvalidRequest,
+    /// `state` of the callback does not match the one sent. The code cannot be trusted
+    StateMismatch,
+    /// User did not finish the dialog in time
+    Timeout,
+    /// Callback without `code` and with an undocumented or without an `error` value
+    Other(String),
+}
+
+impl OAuthError {
+    /// Maps the `error` parameter of a callback to an `OAuthError`
+    pub fn from_code(code: &str) -> OAuthError {
+        match code {
+            "access_denied" => OAuthError::AccessDenied,
+            "unsupported_response_type" => OAuthError::UnsupportedResponseType,
+            "invalid_scope" => OAuthError::InvalidScope,
+            "invalid_request" => OAuthError::InvalidRequest,
+            other => OAuthError::Other(other.to_owned()),
+        }
+    }
+}
+
+impl std::fmt::Display for OAuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            OAuthError::AccessDenied => write!(f, "User denied access to the app"),
+            OAuthError::UnsupportedResponseType => write!(f, "Invalid `response_type` parameter"),
+            OAuthError::InvalidScope => write!(f, "Invalid `scope` parameter"),
+            OAuthError::InvalidRequest => write!(f, "Malformed authorization request"),
+            OAuthError::StateMismatch => write!(
+                f,
+                "State string of response is not the same. Cannot trust the bearer token."
+            ),
+            OAuthError::Timeout => write!(
+                f,
+                "Reached timeout. User did not authorize usage of reddit_api in time"
+            ),
+            OAuthError::Other(e) => write!(f, "Unexpected response from reddit: {}", e),
+        }
+    }
+}
+
+/// Single error entry of a reddit `json.errors` array, e.g.
+/// `["TOO_LONG", "this is too long (max: 10000)", "text"]`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApiError {
+    pub code: String,
+    pub message: String,
+    pub field: Option<String>,
+}
+
+impl ApiError {
+    /// Converts one entry of `json.errors` into an `ApiError`.
+    /// Missing or `null` values are left empty
+    pub fn from_values(values: &[Value]) -> ApiError {
+        let get = |i: usize| values.get(i).and_then(Value::as_str).map(str::to_owned);
+        ApiError {
+            code: get(0).unwrap_or_default(),
+            message: get(1).unwrap_or_default(),
+            field: get(2),
+        }
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "[{}] - {}", self.code, self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_error_from_values() {
+        let values: Vec<Value> =
+            serde_json::from_str(r#"["TOO_LONG", "this is too long", "text"]"#).unwrap();
+        let error = ApiError::from_values(&values);
+        assert_eq!(error.code, "TOO_LONG");
+        assert_eq!(error.message, "this is too long");
+        assert_eq!(error.field, Some("text".to_owned()));
+    }
+
+    #[test]
+    fn test_api_error_from_values_without_field() {
+        let values: Vec<Value> =
+            serde_json::from_str(r#"["RATELIMIT", "slow down", null]"#).unwrap();
+        let error = ApiError::from_values(&values);
+        assert_eq!(error.field, None);
+        assert_eq!(error.to_string(), "[RATELIMIT] - slow down");
+    }
+
+    #[test]
+    fn test_debug_omits_body() {
+        let error = RedditError::Deserialize {
+            error: "missing field `token_type`".to_owned(),
+            body: r#"{"access_token": "secret-access"}"#.to_owned(),
+        };
+        let debug = format!("{:?}", error);
+        assert!(!debug.contains("secret"));
+        assert!(debug.contains("missing field"));
+        let error = RedditError::HttpStatus {
+            status: 500,
+            body: "secret".to_owned(),
+        };
+        assert_eq!(
+            format!("{:?}", error),
+            "HttpStatus { status: 500, body: <6 bytes> }"
+        );
+    }
+}
@@ -1,16 +1,30 @@
 #![allow(clippy::needless_doctest_main)]
+#![allow(clippy::too_many_arguments)]
 #![allow(dead_code)]
 
 #[macro_use]
 extern crate log;
-#[macro_use]
 extern crate failure;
 
+#[cfg(feature = "async")]
+pub mod async_client;
+#[cfg(feature = "async")]
+pub mod async_transport;
 mod callback_server;
 pub mod client;
 mod curl_utils;
+pub mod error;
 pub mod model;
 pub mod oauth2;
+pub mod rate_limit;
+pub mod retry;
+pub mod scope;
+pub mod state_store;
+#[cfg(test)]
+mod test_fixtures;
+pub mod token_store;
+pub mod transport;
+pub mod user_agent;
 pub mod util;
 
 static VERSION: &str = "0.1.3";
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+use super::fullname::ThingId;
+
+/// Account, kind `t2`. Also returned for the authorized user by `/api/v1/me`.
+/// Only a subset of the fields is deserialized
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Account {
+    pub id: ThingId,
+    pub name: String,
+    #[serde(default)]
+    pub created: f64,
+    #[serde(default)]
+    pub created_utc: f64,
+    #[serde(default)]
+    pub link_karma: i64,
+    #[serde(default)]
+    pub comment_karma: i64,
+    #[serde(default)]
+    pub is_gold: bool,
+    #[serde(default)]
+    pub is_mod: bool,
+    #[serde(default)]
+    pub has_verified_email: Option<bool>,
+    #[serde(default)]
+    pub over_18: bool,
+    #[serde(default)]
+    pub icon_img: Option<String>,
+}
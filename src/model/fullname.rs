@@ -0,0 +1,234 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use super::super::error::RedditError;
+
+/// Kind of a thing, encoded as type prefix of its fullname
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ThingKind {
+    COMMENT,
+    ACCOUNT,
+    LINK,
+    MESSAGE,
+    SUBREDDIT,
+    AWARD,
+}
+
+impl ThingKind {
+    /// Type prefix of fullnames of this kind, e.g. `t1` for comments
+    pub fn prefix(self) -> &'static str {
+        match self {
+            ThingKind::COMMENT => "t1",
+            ThingKind::ACCOUNT => "t2",
+            ThingKind::LINK => "t3",
+            ThingKind::MESSAGE => "t4",
+            ThingKind::SUBREDDIT => "t5",
+            ThingKind::AWARD => "t6",
+        }
+    }
+}
+
+impl fmt::Display for ThingKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.prefix())
+    }
+}
+
+impl FromStr for ThingKind {
+    type Err = RedditError;
+
+    fn from_str(prefix: &str) -> Result<ThingKind, RedditError> {
+        match prefix {
+            "t1" => Ok(ThingKind::COMMENT),
+            "t2" => Ok(ThingKind::ACCOUNT),
+            "t3" => Ok(ThingKind::LINK),
+            "t4" => Ok(ThingKind::MESSAGE),
+            "t5" => Ok(ThingKind::SUBREDDIT),
+            "t6" => Ok(ThingKind::AWARD),
+            other => Err(RedditError::InvalidArgument(format!(
+                "Unknown thing kind `{}`",
+                other
+            ))),
+        }
+    }
+}
+
+/// ID of a thing without its kind, a base36 number like `hwuvmf`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ThingId(u64);
+
+impl ThingId {
+    /// ID with the numeric value `id`
+    pub fn new(id: u64) -> ThingId {
+        ThingId(id)
+    }
+    /// Numeric value of the ID
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+    /// Fullname of the thing of `kind` with this ID
+    pub fn fullname(self, kind: ThingKind) -> Fullname {
+        Fullname::new(kind, self)
+    }
+}
+
+impl fmt::Display for ThingId {
+    /// Formats the ID in lowercase base36
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut value = self.0;
+        let mut digits = Vec::new();
+        loop {
+            digits.push(std::char::from_digit((value % 36) as u32, 36).unwrap());
+            value /= 36;
+            if value == 0 {
+                break;
+            }
+        }
+        write!(f, "{}", digits.iter().rev().collect::<String>())
+    }
+}
+
+impl FromStr for ThingId {
+    type Err = RedditError;
+
+    /// Parses a lowercase base36 ID like `hwuvmf`
+    fn from_str(id: &str) -> Result<ThingId, RedditError> {
+        let invalid = || RedditError::InvalidArgument(format!("Invalid thing id `{}`", id));
+        if id.is_empty()
+            || !id
+                .bytes()
+                .all(|b| b.is_ascii_digit() || b.is_ascii_lowercase())
+        {
+            return Err(invalid());
+        }
+        u64::from_str_radix(id, 36)
+            .map(ThingId)
+            .map_err(|_| invalid())
+    }
+}
+
+impl TryFrom<String> for ThingId {
+    type Error = RedditError;
+
+    fn try_from(id: String) -> Result<ThingId, RedditError> {
+        id.parse()
+    }
+}
+
+impl From<ThingId> for String {
+    fn from(id: ThingId) -> String {
+        id.to_string()
+    }
+}
+
+/// Fullname of a thing, its kind prefix and ID like `t3_hwuvmf`
+///
+/// # Example
+/// ```
+/// use rsreddit::model::fullname::{Fullname, ThingKind};
+/// let fullname: Fullname = "t3_hwuvmf".parse().unwrap();
+/// assert_eq!(fullname.kind(), ThingKind::LINK);
+/// assert_eq!(fullname.id().to_string(), "hwuvmf");
+/// assert!("t3_HWUVMF".parse::<Fullname>().is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Fullname {
+    kind: ThingKind,
+    id: ThingId,
+}
+
+impl Fullname {
+    /// Fullname of the thing of `kind` with ID `id`
+    pub fn new(kind: ThingKind, id: ThingId) -> Fullname {
+        Fullname { kind, id }
+    }
+    /// Kind of the thing
+    pub fn kind(&self) -> ThingKind {
+        self.kind
+    }
+    /// ID of the thing without kind prefix
+    pub fn id(&self) -> ThingId {
+        self.id
+    }
+    /// Fails with `RedditError::InvalidArgument` unless the thing is of one of `kinds`
+    pub fn expect_kind(&self, kinds: &[ThingKind]) -> Result<&Fullname, RedditError> {
+        if kinds.contains(&self.kind) {
+            Ok(self)
+        } else {
+            let expected: Vec<&str> = kinds.iter().map(|kind| kind.prefix()).collect();
+            Err(RedditError::InvalidArgument(format!(
+                "Expected fullname of kind {}, got `{}`",
+                expected.join(" or "),
+                self
+            )))
+        }
+    }
+}
+
+impl fmt::Display for Fullname {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}_{}", self.kind, self.id)
+    }
+}
+
+impl FromStr for Fullname {
+    type Err = RedditError;
+
+    fn from_str(fullname: &str) -> Result<Fullname, RedditError> {
+        let mut parts = fullname.splitn(2, '_');
+        match (parts.next(), parts.next()) {
+            (Some(kind), Some(id)) => Ok(Fullname::new(kind.parse()?, id.parse()?)),
+            _ => Err(RedditError::InvalidArgument(format!(
+                "Invalid fullname `{}`",
+                fullname
+            ))),
+        }
+    }
+}
+
+impl TryFrom<String> for Fullname {
+    type Error = RedditError;
+
+    fn try_from(fullname: String) -> Result<Fullname, RedditError> {
+        fullname.parse()
+    }
+}
+
+impl From<Fullname> for String {
+    fn from(fullname: Fullname) -> String {
+        fullname.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base36_round_trip() {
+        let id: ThingId = "hwuvmf".parse().unwrap();
+        assert_eq!(id.as_u64(), 1_083_113_367);
+        assert_eq!(ThingId::new(1_083_113_367).to_string(), "hwuvmf");
+        assert_eq!(ThingId::new(0).to_string(), "0");
+        assert!("".parse::<ThingId>().is_err());
+        assert!("zzzzzzzzzzzzzzzz".parse::<ThingId>().is_err());
+    }
+
+    #[test]
+    fn test_parse_fullname() {
+        let fullname: Fullname = "t1_fz3jzfi".parse().unwrap();
+        assert_eq!(fullname.kind(), ThingKind::COMMENT);
+        assert_eq!(fullname.to_string(), "t1_fz3jzfi");
+        assert!("t9_abc".parse::<Fullname>().is_err());
+        assert!("abc".parse::<Fullname>().is_err());
+        assert!("t1_".parse::<Fullname>().is_err());
+        assert!(fullname.expect_kind(&[ThingKind::LINK]).is_err());
+        let parsed: Vec<Fullname> = serde_json::from_str(r#"["t3_hwuvmf"]"#).unwrap();
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), r#"["t3_hwuvmf"]"#);
+    }
+}
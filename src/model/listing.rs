@@ -1,6 +1,7 @@
-use super::super::model::thing::Thing;
+use super::super::model::fullname::Fullname;
+use super::super::model::thing::{More, Thing};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use std::collections::HashMap;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Listing {
@@ -12,18 +13,111 @@ pub struct Listing {
 pub struct Data {
     pub modhash: String,
     pub dist: Option<u32>,
-    pub before: Option<String>,
-    pub after: Option<String>,
+    pub before: Option<Fullname>,
+    pub after: Option<Fullname>,
     pub children: Vec<Child>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct Child {
-    pub kind: String,
-    pub data: Thing,
-}
+/// Child of a listing, dispatched on its `kind`
+pub type Child = Thing;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ListingCollection {
     pub listings: Vec<Listing>,
 }
+
+impl Listing {
+    /// Listing of `children` without pagination
+    pub fn from_children(children: Vec<Thing>) -> Listing {
+        Listing {
+            kind: "Listing".to_owned(),
+            data: Data {
+                modhash: "".to_owned(),
+                dist: None,
+                before: None,
+                after: None,
+                children,
+            },
+        }
+    }
+
+    /// All `more` stubs in the listing and the replies of its comments, in depth-first order
+    pub fn more_stubs(&self) -> Vec<&More> {
+        let mut stubs = Vec::new();
+        for child in &self.data.children {
+            match child {
+                Thing::More(more) => stubs.push(more),
+                Thing::Comment(comment) => {
+                    if let Some(replies) = &comment.replies {
+                        stubs.extend(replies.more_stubs());
+                    }
+                }
+                _ => {}
+            }
+        }
+        stubs
+    }
+
+    /// Replaces the `more` stub `stub` with `things`, wherever it is in the tree.
+    /// Returns `things` again if the stub was not found
+    pub fn replace_more(&mut self, stub: &More, things: Vec<Thing>) -> Result<(), Vec<Thing>> {
+        let position = self.data.children.iter().position(|child| match child {
+            Thing::More(more) => more.name == stub.name && more.parent_id == stub.parent_id,
+            _ => false,
+        });
+        if let Some(position) = position {
+            self.data.children.splice(position..=position, things);
+            return Ok(());
+        }
+        let mut things = things;
+        for child in &mut self.data.children {
+            if let Thing::Comment(comment) = child {
+                if let Some(replies) = &mut comment.replies {
+                    match replies.replace_more(stub, things) {
+                        Ok(()) => return Ok(()),
+                        Err(rest) => things = rest,
+                    }
+                }
+            }
+        }
+        Err(things)
+    }
+}
+
+/// Nests the flat `things` below `parent` by their `parent_id`, keeping their order.
+/// Things whose parent is neither `parent` nor one of `things` are dropped
+pub fn nest_comments(parent: &Fullname, things: Vec<Thing>) -> Vec<Thing> {
+    let mut by_parent: HashMap<Fullname, Vec<Thing>> = HashMap::new();
+    for thing in things {
+        match thing.parent_id() {
+            Some(parent_id) => by_parent.entry(*parent_id).or_default().push(thing),
+            None => debug!("Dropping {} thing without parent", thing.kind()),
+        }
+    }
+    let nested = take_children(parent, &mut by_parent);
+    for (parent_id, orphans) in by_parent {
+        debug!(
+            "Dropping {} things of unknown parent {}",
+            orphans.len(),
+            parent_id
+        );
+    }
+    nested
+}
+
+/// Removes the children of `parent` from `by_parent`, with their replies attached
+fn take_children(parent: &Fullname, by_parent: &mut HashMap<Fullname, Vec<Thing>>) -> Vec<Thing> {
+    let mut children = by_parent.remove(parent).unwrap_or_default();
+    for child in &mut children {
+        if let Thing::Comment(comment) = child {
+            let replies = take_children(&comment.name, by_parent);
+            if !replies.is_empty() {
+                match &mut comment.replies {
+                    Some(listing) => listing.data.children.extend(replies),
+                    None => comment.replies = Some(Listing::from_children(replies)),
+                }
+            }
+        }
+    }
+    children
+}
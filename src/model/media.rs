@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+
+/// Video or embedded content of a link, `media` and `secure_media` of a `Link`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Media {
+    /// Video hosted on v.redd.it
+    pub reddit_video: Option<RedditVideo>,
+    /// Provider of embedded content, e.g. `youtube.com`
+    #[serde(rename = "type")]
+    pub provider: Option<String>,
+    pub oembed: Option<OEmbed>,
+}
+
+/// Video hosted by reddit
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RedditVideo {
+    /// Direct MP4 URL, without audio track
+    pub fallback_url: String,
+    /// MPEG-DASH playlist, including audio
+    pub dash_url: Option<String>,
+    /// HLS playlist, including audio
+    pub hls_url: Option<String>,
+    pub scrubber_media_url: Option<String>,
+    #[serde(default)]
+    pub width: u32,
+    #[serde(default)]
+    pub height: u32,
+    /// Duration in seconds
+    #[serde(default)]
+    pub duration: u32,
+    pub bitrate_kbps: Option<u32>,
+    #[serde(default)]
+    pub is_gif: bool,
+    pub transcoding_status: Option<String>,
+}
+
+/// Embedded content of another site, see https://oembed.com
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OEmbed {
+    /// `video`, `photo`, `link` or `rich`
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub provider_name: Option<String>,
+    pub provider_url: Option<String>,
+    pub title: Option<String>,
+    pub author_name: Option<String>,
+    pub author_url: Option<String>,
+    /// HTML snippet embedding the content
+    pub html: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub thumbnail_url: Option<String>,
+    pub thumbnail_width: Option<u32>,
+    pub thumbnail_height: Option<u32>,
+}
+
+/// Embed snippet of a link, `media_embed` and `secure_media_embed` of a `Link`.
+/// Empty for links without embedded content
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MediaEmbed {
+    /// HTML snippet embedding the content
+    pub content: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub scrolling: Option<bool>,
+    pub media_domain_url: Option<String>,
+}
+
+/// Order and captions of the images of a gallery post
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GalleryData {
+    pub items: Vec<GalleryItem>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GalleryItem {
+    /// Key of the image in `media_metadata` of the link
+    pub media_id: String,
+    pub caption: Option<String>,
+    pub outbound_url: Option<String>,
+}
+
+/// Image or animation uploaded to reddit, values of `media_metadata` of a `Link`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MediaMetadata {
+    /// `valid` once reddit processed the upload
+    #[serde(default)]
+    pub status: String,
+    /// `Image` or `AnimatedImage`
+    #[serde(rename = "e")]
+    pub kind: Option<String>,
+    /// Mime type, e.g. `image/jpg`
+    #[serde(rename = "m")]
+    pub mime_type: Option<String>,
+    /// Original upload
+    #[serde(rename = "s")]
+    pub source: Option<MediaSource>,
+    /// Scaled down versions, smallest first
+    #[serde(rename = "p", default)]
+    pub previews: Vec<MediaSource>,
+}
+
+impl MediaMetadata {
+    /// Direct URL of the original upload. MP4 for animations, if available
+    pub fn url(&self) -> Option<String> {
+        let source = self.source.as_ref()?;
+        source
+            .mp4
+            .as_ref()
+            .or(source.gif.as_ref())
+            .or(source.url.as_ref())
+            .map(|url| unescape_url(url))
+    }
+}
+
+/// Size and location of an entry of `MediaMetadata`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MediaSource {
+    #[serde(rename = "x")]
+    pub width: Option<u32>,
+    #[serde(rename = "y")]
+    pub height: Option<u32>,
+    /// URL of images
+    #[serde(rename = "u")]
+    pub url: Option<String>,
+    /// URLs of animations
+    pub gif: Option<String>,
+    pub mp4: Option<String>,
+}
+
+/// Undoes the HTML escaping of URLs in responses requested without `raw_json=1`
+pub(crate) fn unescape_url(url: &str) -> String {
+    url.replace("&amp;", "&")
+}
@@ -1,7 +1,12 @@
 //! All Reddit API endpoint response objects
+pub mod account;
+pub mod fullname;
 pub mod listing;
+pub mod media;
 pub mod preview;
 pub mod responses;
+pub mod scope_description;
 pub mod sort_time;
 pub mod thing;
+pub mod thread;
 pub mod token;
@@ -1,7 +1,60 @@
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use std::collections::BTreeMap;
+
+use super::media::{unescape_url, RedditVideo};
+
+/// Preview images reddit generated for a link
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct Preview {
+    #[serde(default)]
     pub enabled: bool,
-    pub images: Vec<String>, //TODO: create image model
+    #[serde(default)]
+    pub images: Vec<PreviewImage>,
+    /// MP4 version of gifs and videos hosted elsewhere
+    pub reddit_video_preview: Option<RedditVideo>,
+}
+
+impl Preview {
+    /// URL of the first image in full resolution
+    pub fn source_url(&self) -> Option<String> {
+        self.images
+            .first()
+            .map(|image| unescape_url(&image.source.url))
+    }
+}
+
+/// Image in its original size, scaled down resolutions and variants
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PreviewImage {
+    pub id: String,
+    pub source: ImageSource,
+    /// Scaled down versions, smallest first
+    #[serde(default)]
+    pub resolutions: Vec<ImageSource>,
+    /// Other formats of the image, keyed by `gif`, `mp4`, `nsfw` or `obfuscated`
+    #[serde(default)]
+    pub variants: BTreeMap<String, ImageVariant>,
+}
+
+impl PreviewImage {
+    /// Variant `name`, e.g. `mp4` for animated images
+    pub fn variant(&self, name: &str) -> Option<&ImageVariant> {
+        self.variants.get(name)
+    }
+}
+
+/// Format of a `PreviewImage`, e.g. the blurred `nsfw` version
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ImageVariant {
+    pub source: ImageSource,
+    #[serde(default)]
+    pub resolutions: Vec<ImageSource>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ImageSource {
+    /// HTML escaped unless requested with `raw_json=1`
+    pub url: String,
+    pub width: u32,
+    pub height: u32,
 }
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use super::super::super::error::ApiError;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CommentResponse {
     pub json: Option<ErrorResponse>,
@@ -8,5 +10,15 @@ pub struct CommentResponse {
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ErrorResponse {
-    pub errors: Vec<Vec<String>>,
+    pub errors: Vec<Vec<Value>>,
+}
+
+impl ErrorResponse {
+    /// Converts raw `json.errors` entries into typed `ApiError`s
+    pub fn api_errors(&self) -> Vec<ApiError> {
+        self.errors
+            .iter()
+            .map(|e| ApiError::from_values(e))
+            .collect()
+    }
 }
@@ -1 +1,2 @@
 pub mod comment_response;
+pub mod more_children_response;
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+use super::super::thing::Thing;
+use super::comment_response::ErrorResponse;
+
+/// Answer of `/api/morechildren`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MoreChildrenResponse {
+    pub json: MoreChildrenJson,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MoreChildrenJson {
+    #[serde(flatten)]
+    pub error: ErrorResponse,
+    pub data: Option<MoreChildrenData>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MoreChildrenData {
+    /// Comments and further `more` stubs, flat and in depth-first order
+    pub things: Vec<Thing>,
+}
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+/// Entry of the scope catalogue at `/api/v1/scopes`, e.g.
+/// `{"id": "read", "name": "Read Content", "description": "Access posts and comments through my account."}`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScopeDescription {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+}
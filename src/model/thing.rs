@@ -1,142 +1,809 @@
+use super::super::model::account::Account;
+use super::super::model::fullname::{Fullname, ThingId};
 use super::super::model::listing::Listing;
+use super::super::model::media::{unescape_url, GalleryData, Media, MediaEmbed, MediaMetadata};
 use super::super::model::preview::Preview;
-use std::fmt::Display;
-use std::str::FromStr;
 
-#[macro_use]
-use serde::de::{self, Deserializer};
+use serde::de::{self, DeserializeOwned, Deserializer};
+use serde::ser::{SerializeStruct, Serializer};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
 
+/// Object of the reddit API, dispatched on its `kind`.
+/// See: https://www.reddit.com/dev/api#fullnames
+// Listings mostly hold links and comments, so boxing them would not save memory
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug)]
+pub enum Thing {
+    /// Kind `t1`
+    Comment(Comment),
+    /// Kind `t2`
+    Account(Account),
+    /// Kind `t3`
+    Link(Link),
+    /// Kind `t4`
+    Message(Message),
+    /// Kind `t5`
+    Subreddit(Subreddit),
+    /// Kind `t6`
+    Award(Award),
+    /// Stub for comments left out of a comment tree, kind `more`
+    More(More),
+    LabeledMulti(LabeledMulti),
+    /// Kind not modelled by this crate, with its raw `data`
+    Unknown {
+        kind: String,
+        data: Value,
+    },
+}
+
+/// `Thing` before dispatching on `kind`
+#[derive(Deserialize)]
+struct RawThing {
+    kind: String,
+    #[serde(default)]
+    data: Value,
+}
+
+impl<'de> Deserialize<'de> for Thing {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Thing, D::Error> {
+        let raw = RawThing::deserialize(deserializer)?;
+        let data = raw.data;
+        let thing = match raw.kind.as_str() {
+            "t1" => Thing::Comment(from_value(data)?),
+            "t2" => Thing::Account(from_value(data)?),
+            "t3" => Thing::Link(from_value(data)?),
+            "t4" => Thing::Message(from_value(data)?),
+            "t5" => Thing::Subreddit(from_value(data)?),
+            "t6" => Thing::Award(from_value(data)?),
+            "more" => Thing::More(from_value(data)?),
+            "LabeledMulti" => Thing::LabeledMulti(from_value(data)?),
+            _ => Thing::Unknown {
+                kind: raw.kind,
+                data,
+            },
+        };
+        Ok(thing)
+    }
+}
+
+impl Serialize for Thing {
+    /// Serializes the thing as `{"kind": ..., "data": ...}`, like reddit sends it
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Thing", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        match self {
+            Thing::Comment(comment) => state.serialize_field("data", comment)?,
+            Thing::Account(account) => state.serialize_field("data", account)?,
+            Thing::Link(link) => state.serialize_field("data", link)?,
+            Thing::Message(message) => state.serialize_field("data", message)?,
+            Thing::Subreddit(subreddit) => state.serialize_field("data", subreddit)?,
+            Thing::Award(award) => state.serialize_field("data", award)?,
+            Thing::More(more) => state.serialize_field("data", more)?,
+            Thing::LabeledMulti(multi) => state.serialize_field("data", multi)?,
+            Thing::Unknown { data, .. } => state.serialize_field("data", data)?,
+        }
+        state.end()
+    }
+}
+
+impl Thing {
+    /// Kind prefix of the thing, e.g. `t1` for comments
+    pub fn kind(&self) -> &str {
+        match self {
+            Thing::Comment(_) => "t1",
+            Thing::Account(_) => "t2",
+            Thing::Link(_) => "t3",
+            Thing::Message(_) => "t4",
+            Thing::Subreddit(_) => "t5",
+            Thing::Award(_) => "t6",
+            Thing::More(_) => "more",
+            Thing::LabeledMulti(_) => "LabeledMulti",
+            Thing::Unknown { kind, .. } => kind,
+        }
+    }
+    /// Comment, if the thing is one
+    pub fn as_comment(&self) -> Option<&Comment> {
+        match self {
+            Thing::Comment(comment) => Some(comment),
+            _ => None,
+        }
+    }
+    /// Link, if the thing is one
+    pub fn as_link(&self) -> Option<&Link> {
+        match self {
+            Thing::Link(link) => Some(link),
+            _ => None,
+        }
+    }
+    /// More stub, if the thing is one
+    pub fn as_more(&self) -> Option<&More> {
+        match self {
+            Thing::More(more) => Some(more),
+            _ => None,
+        }
+    }
+    /// Fullname of the thing a comment, message or `more` stub replies to
+    pub fn parent_id(&self) -> Option<&Fullname> {
+        match self {
+            Thing::Comment(comment) => Some(&comment.parent_id),
+            Thing::Message(message) => message.parent_id.as_ref(),
+            Thing::More(more) => Some(&more.parent_id),
+            _ => None,
+        }
+    }
+}
+
+/// Things with a creation date
+pub trait Created {
+    /// Creation time as unix timestamp in local time of the server
+    fn created(&self) -> f64;
+    /// Creation time as unix timestamp in UTC
+    fn created_utc(&self) -> f64;
+}
+
+/// Things which can be voted on
+pub trait Votable {
+    /// Number of upvotes
+    fn ups(&self) -> i32;
+    /// Number of downvotes. Reddit reports `0` for most things nowadays
+    fn downs(&self) -> i32;
+    /// Vote of the authorized user. `Some(true)` if upvoted, `Some(false)` if downvoted, `None` otherwise
+    fn likes(&self) -> Option<bool>;
+}
+
+macro_rules! impl_created {
+    ($($thing:ty),*) => {
+        $(impl Created for $thing {
+            fn created(&self) -> f64 {
+                self.created
+            }
+            fn created_utc(&self) -> f64 {
+                self.created_utc
+            }
+        })*
+    };
+}
+
+macro_rules! impl_votable {
+    ($($thing:ty),*) => {
+        $(impl Votable for $thing {
+            fn ups(&self) -> i32 {
+                self.ups
+            }
+            fn downs(&self) -> i32 {
+                self.downs
+            }
+            fn likes(&self) -> Option<bool> {
+                self.likes
+            }
+        })*
+    };
+}
+
+impl_created!(Comment, Account, Link, Message, Subreddit, LabeledMulti);
+impl_votable!(Comment, Link);
+
+/// Comment, kind `t1`
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct Thing {
-    pub id: String,
+pub struct Comment {
+    pub id: ThingId,
+    pub name: Fullname,
+    #[serde(default)]
+    pub author: String,
+    /// Fullname of the author's account. `None` for deleted authors
+    pub author_fullname: Option<Fullname>,
+    pub author_flair_text: Option<String>,
+    #[serde(default)]
+    pub body: String,
+    pub body_html: Option<String>,
+    /// Fullname of the link the comment belongs to
+    pub link_id: Fullname,
+    /// Fullname of the link or comment replied to
+    pub parent_id: Fullname,
+    #[serde(default)]
     pub subreddit: String,
+    pub subreddit_id: Option<Fullname>,
+    #[serde(default)]
+    pub subreddit_name_prefixed: String,
+    #[serde(default)]
     pub permalink: String,
-    pub author: String,
-    pub author_fullname: String,
+    #[serde(default)]
+    pub score: i32,
+    #[serde(default)]
+    pub score_hidden: bool,
+    #[serde(default)]
     pub ups: i32,
-    pub downs: usize,
-    pub saved: bool,
+    #[serde(default)]
+    pub downs: i32,
+    pub likes: Option<bool>,
+    #[serde(default)]
+    pub controversiality: u32,
+    #[serde(default)]
     pub gilded: usize,
-    pub score: Option<i32>,
-    pub clicked: Option<bool>,
-    pub title: Option<String>,
-    pub is_self: Option<bool>,
+    #[serde(default)]
+    pub total_awards_received: usize,
+    #[serde(default)]
+    pub saved: bool,
+    #[serde(default)]
     pub archived: bool,
+    #[serde(default)]
+    pub locked: bool,
+    #[serde(default)]
+    pub stickied: bool,
+    #[serde(default)]
+    pub collapsed: bool,
+    #[serde(default)]
+    pub is_submitter: bool,
+    #[serde(default)]
+    pub send_replies: bool,
+    pub distinguished: Option<String>,
+    #[serde(default)]
+    pub can_gild: bool,
+    #[serde(default)]
     pub no_follow: bool,
-    pub is_crosspostable: Option<bool>,
-    pub pinned: Option<bool>,
-    pub over_18: Option<bool>,
-    pub is_video: Option<bool>,
-    pub link_flair_richtext: Option<Vec<LinkFlairRichtext>>,
+    pub author_flair_type: Option<String>,
+    pub author_flair_template_id: Option<String>,
+    pub author_patreon_flair: Option<bool>,
+    pub top_awarded_type: Option<String>,
+    /// Awards given, with how often each was given in `Award.count`
+    #[serde(default)]
+    pub all_awardings: Vec<Award>,
+    /// Number of gildings by award id, e.g. `gid_1`
+    #[serde(default)]
+    pub gildings: BTreeMap<String, usize>,
+    #[serde(default)]
+    pub awarders: Vec<String>,
+    #[serde(default)]
+    pub treatment_tags: Vec<String>,
+    /// Reports by moderators, as `[reason, moderator]`. Only visible to moderators
+    #[serde(default)]
+    pub mod_reports: Vec<Value>,
+    /// Reports by users, as `[reason, count, ...]`. Only visible to moderators
+    #[serde(default)]
+    pub user_reports: Vec<Value>,
+    pub num_reports: Option<usize>,
+    pub report_reasons: Option<Vec<String>>,
+    pub removed_by: Option<String>,
+    /// Why the thing is removed, e.g. `moderator`, `deleted` or `automod_filtered`
+    pub removed_by_category: Option<String>,
+    pub removal_reason: Option<String>,
+    /// Moderator who removed the thing, or `true` if it was caught by the spam filter
+    pub banned_by: Option<Value>,
+    pub banned_at_utc: Option<f64>,
+    pub approved_at_utc: Option<f64>,
+    pub mod_note: Option<String>,
+    pub mod_reason_by: Option<String>,
+    pub mod_reason_title: Option<String>,
+    /// Unix timestamp of the last edit, `None` if never edited
+    #[serde(default, deserialize_with = "false_or_timestamp")]
+    pub edited: Option<f64>,
+    /// Depth in the comment tree, `0` for top level comments
+    pub depth: Option<u32>,
+    #[serde(default)]
+    pub created: f64,
+    #[serde(default)]
+    pub created_utc: f64,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub replies: Option<Listing>,
+}
+
+/// Link, kind `t3`. Either a link to another site or a self post
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Link {
+    pub id: ThingId,
+    pub name: Fullname,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub author: String,
+    /// Fullname of the author's account. `None` for deleted authors
+    pub author_fullname: Option<Fullname>,
+    pub author_flair_text: Option<String>,
+    pub author_flair_background_color: Option<String>,
+    pub author_flair_text_color: Option<String>,
+    #[serde(default)]
+    pub subreddit: String,
+    pub subreddit_id: Option<Fullname>,
+    #[serde(default)]
     pub subreddit_name_prefixed: String,
-    pub name: String,
-    pub quarantine: Option<bool>,
-    pub link_flair_text_color: Option<String>,
-    pub upvote_ratio: Option<f32>,
+    #[serde(default)]
     pub subreddit_type: String,
+    pub subreddit_subscribers: Option<usize>,
+    #[serde(default)]
+    pub permalink: String,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub domain: String,
+    #[serde(default)]
+    pub is_self: bool,
+    #[serde(default)]
+    pub selftext: String,
+    pub selftext_html: Option<String>,
+    pub thumbnail: Option<String>,
+    pub thumbnail_width: Option<usize>,
+    pub thumbnail_height: Option<usize>,
+    pub post_hint: Option<String>,
+    #[serde(default)]
+    pub is_video: bool,
+    #[serde(default)]
+    pub score: i32,
+    #[serde(default)]
+    pub hide_score: bool,
+    #[serde(default)]
+    pub ups: i32,
+    #[serde(default)]
+    pub downs: i32,
+    pub likes: Option<bool>,
+    pub upvote_ratio: Option<f32>,
+    #[serde(default)]
+    pub num_comments: usize,
+    #[serde(default)]
+    pub num_crossposts: usize,
+    pub view_count: Option<usize>,
+    #[serde(default)]
+    pub gilded: usize,
+    #[serde(default)]
     pub total_awards_received: usize,
-    pub link_flair_background_color: Option<String>,
-    pub created: f64,
-    pub can_gild: bool,
-    pub spoiler: Option<bool>,
+    #[serde(default)]
+    pub over_18: bool,
+    #[serde(default)]
+    pub spoiler: bool,
+    #[serde(default)]
+    pub saved: bool,
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(default)]
+    pub clicked: bool,
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
     pub locked: bool,
-    pub is_robot_indexable: Option<bool>,
-    pub is_reddit_media_domain: Option<bool>,
-    pub is_meta: Option<bool>,
-    pub media_only: Option<bool>,
-    pub num_comments: Option<usize>,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub stickied: bool,
+    #[serde(default)]
+    pub quarantine: bool,
+    #[serde(default)]
+    pub contest_mode: bool,
+    #[serde(default)]
+    pub is_original_content: bool,
+    #[serde(default)]
     pub send_replies: bool,
+    pub distinguished: Option<String>,
+    pub suggested_sort: Option<String>,
+    pub link_flair_text: Option<String>,
+    pub link_flair_css_class: Option<String>,
+    pub link_flair_template_id: Option<String>,
+    pub link_flair_text_color: Option<String>,
+    pub link_flair_background_color: Option<String>,
+    #[serde(default)]
+    pub link_flair_richtext: Vec<LinkFlairRichtext>,
+    pub category: Option<String>,
+    pub content_categories: Option<Vec<String>>,
+    pub discussion_type: Option<String>,
+    pub link_flair_type: Option<String>,
     pub whitelist_status: Option<String>,
-    pub subreddit_id: String,
-    pub contest_mode: Option<bool>,
-    pub mod_reports: Vec<String>,
-    pub author_patreon_flair: bool,
     pub parent_whitelist_status: Option<String>,
-    pub stickied: bool,
-    pub subreddit_subscribers: Option<usize>,
-    pub created_utc: f64,
-    pub is_original_content: Option<bool>,
-    pub author_flair_type: String,
-    pub user_reports: Vec<String>,
-    pub treatment_tags: Vec<String>,
-    pub num_crossposts: Option<usize>,
-    pub awarders: Vec<String>,
-    pub hidden: Option<bool>,
-    pub pwls: Option<usize>,
-    pub hide_score: Option<bool>,
-    pub domain: Option<String>,
-    pub allow_live_comments: Option<bool>,
-    pub link_flair_type: Option<String>,
-    pub wls: Option<usize>,
-    pub selftext: Option<String>,
-    pub url: Option<String>,
-    #[serde(default = "default_reply")]
-    pub replies: Option<Listing>,
-    pub body: Option<String>,
-    pub body_html: Option<String>,
-    pub thumbnail_width: Option<usize>,
+    pub wls: Option<u32>,
+    pub pwls: Option<u32>,
+    #[serde(default)]
+    pub is_crosspostable: bool,
+    #[serde(default)]
+    pub is_robot_indexable: bool,
+    #[serde(default)]
+    pub is_reddit_media_domain: bool,
+    #[serde(default)]
+    pub is_meta: bool,
+    #[serde(default)]
+    pub media_only: bool,
+    #[serde(default)]
+    pub allow_live_comments: bool,
+    #[serde(default)]
+    pub can_gild: bool,
+    #[serde(default)]
+    pub no_follow: bool,
+    pub author_flair_type: Option<String>,
     pub author_flair_template_id: Option<String>,
-    pub post_hint: Option<String>,
-    pub approved_at_utc: Option<String>,
-    pub link_flair_css_class: Option<String>,
-    pub thumbnail_height: Option<usize>,
-    pub mod_reason_title: Option<String>,
-    pub mod_note: Option<String>,
-    pub banned_by: Option<String>,
-    pub category: Option<String>,
-    pub selftext_html: Option<String>,
-    pub likes: Option<String>,
-    pub suggested_sort: Option<String>,
-    pub banned_at_utc: Option<f64>,
+    pub author_patreon_flair: Option<bool>,
     pub top_awarded_type: Option<String>,
-    pub view_count: Option<usize>,
-    pub link_flair_template_id: Option<String>,
-    pub author_flair_text: Option<String>,
-    pub removed_by: Option<String>,
+    /// Awards given, with how often each was given in `Award.count`
+    #[serde(default)]
+    pub all_awardings: Vec<Award>,
+    /// Number of gildings by award id, e.g. `gid_1`
+    #[serde(default)]
+    pub gildings: BTreeMap<String, usize>,
+    #[serde(default)]
+    pub awarders: Vec<String>,
+    #[serde(default)]
+    pub treatment_tags: Vec<String>,
+    /// Reports by moderators, as `[reason, moderator]`. Only visible to moderators
+    #[serde(default)]
+    pub mod_reports: Vec<Value>,
+    /// Reports by users, as `[reason, count, ...]`. Only visible to moderators
+    #[serde(default)]
+    pub user_reports: Vec<Value>,
     pub num_reports: Option<usize>,
-    pub distinguished: Option<String>,
-    pub mod_reason_by: Option<String>,
-    pub removal_reason: Option<String>,
-    pub report_reasons: Option<String>,
-    pub author_flair_background_color: Option<String>,
-    pub discussion_type: Option<String>,
-    pub author_flair_text_color: Option<String>,
-    #[serde(skip_deserializing, skip_serializing)]
-    pub content_categories: Option<String>,
-    #[serde(skip_deserializing, skip_serializing)]
+    pub report_reasons: Option<Vec<String>>,
+    pub removed_by: Option<String>,
+    /// Why the thing is removed, e.g. `moderator`, `deleted` or `automod_filtered`
     pub removed_by_category: Option<String>,
-    #[serde(skip_deserializing, skip_serializing)]
-    pub preview: Preview,
-    #[serde(skip_deserializing, skip_serializing)]
-    pub all_awardings: Vec<String>,
-    #[serde(skip_deserializing, skip_serializing)]
-    pub gildings: String,
-    #[serde(skip_deserializing, skip_serializing)]
-    pub media: Option<String>,
-    #[serde(skip_deserializing, skip_serializing)]
-    pub media_metadata: Vec<String>,
-    #[serde(skip_deserializing, skip_serializing)]
-    pub secure_media: Option<String>,
-    #[serde(skip_deserializing, skip_serializing)]
-    pub media_embed: Vec<String>,
-}
-fn from_str<'de, T, D>(deserializer: D) -> Result<T, D::Error>
-where
-    T: FromStr,
-    T::Err: Display,
-    D: Deserializer<'de>,
-{
-    let s = String::deserialize(deserializer)?;
-    T::from_str(&s).map_err(de::Error::custom)
+    pub removal_reason: Option<String>,
+    /// Moderator who removed the thing, or `true` if it was caught by the spam filter
+    pub banned_by: Option<Value>,
+    pub banned_at_utc: Option<f64>,
+    pub approved_at_utc: Option<f64>,
+    pub mod_note: Option<String>,
+    pub mod_reason_by: Option<String>,
+    pub mod_reason_title: Option<String>,
+    /// Unix timestamp of the last edit, `None` if never edited
+    #[serde(default, deserialize_with = "false_or_timestamp")]
+    pub edited: Option<f64>,
+    #[serde(default)]
+    pub created: f64,
+    #[serde(default)]
+    pub created_utc: f64,
+    pub preview: Option<Preview>,
+    /// Video hosted by reddit or embedded from another site
+    pub media: Option<Media>,
+    pub secure_media: Option<Media>,
+    #[serde(default)]
+    pub media_embed: MediaEmbed,
+    #[serde(default)]
+    pub secure_media_embed: MediaEmbed,
+    #[serde(default)]
+    pub is_gallery: bool,
+    pub gallery_data: Option<GalleryData>,
+    /// Images uploaded with the post, keyed by media id
+    pub media_metadata: Option<BTreeMap<String, MediaMetadata>>,
+}
+
+impl Link {
+    /// Direct URLs of the images of a gallery post, in the order of the gallery
+    pub fn gallery_urls(&self) -> Vec<String> {
+        match (&self.gallery_data, &self.media_metadata) {
+            (Some(gallery), Some(metadata)) => gallery
+                .items
+                .iter()
+                .filter_map(|item| metadata.get(&item.media_id))
+                .filter_map(MediaMetadata::url)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Best direct URL of the media of the post, checked in this order:
+    /// reddit video, first gallery image, linked image, video preview, image preview.
+    /// `None` for posts without media, e.g. most self posts
+    pub fn best_media_url(&self) -> Option<String> {
+        let video = self
+            .secure_media
+            .as_ref()
+            .or(self.media.as_ref())
+            .and_then(|media| media.reddit_video.as_ref());
+        if let Some(video) = video {
+            return Some(unescape_url(&video.fallback_url));
+        }
+        if let Some(url) = self.gallery_urls().into_iter().next() {
+            return Some(url);
+        }
+        if self.post_hint.as_deref() == Some("image") || is_image_url(&self.url) {
+            return Some(unescape_url(&self.url));
+        }
+        let preview = self.preview.as_ref()?;
+        // Gifs hosted elsewhere are converted to MP4 by reddit
+        if let Some(video) = &preview.reddit_video_preview {
+            return Some(unescape_url(&video.fallback_url));
+        }
+        let mp4 = preview
+            .images
+            .first()
+            .and_then(|image| image.variant("mp4"));
+        if let Some(mp4) = mp4 {
+            return Some(unescape_url(&mp4.source.url));
+        }
+        preview.source_url()
+    }
+}
+
+/// Private message or comment reply in the inbox, kind `t4`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Message {
+    pub id: ThingId,
+    pub name: Fullname,
+    /// Sender. `None` for messages sent by reddit itself
+    pub author: Option<String>,
+    /// Recipient, a username or a prefixed subreddit
+    pub dest: Option<String>,
+    #[serde(default)]
+    pub subject: String,
+    #[serde(default)]
+    pub body: String,
+    pub body_html: Option<String>,
+    /// Permalink of the comment, if the message is a comment reply
+    #[serde(default)]
+    pub context: String,
+    pub subreddit: Option<String>,
+    pub first_message_name: Option<Fullname>,
+    pub parent_id: Option<Fullname>,
+    pub distinguished: Option<String>,
+    /// Whether the message was not read yet
+    #[serde(default)]
+    pub new: bool,
+    #[serde(default)]
+    pub was_comment: bool,
+    #[serde(default)]
+    pub created: f64,
+    #[serde(default)]
+    pub created_utc: f64,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub replies: Option<Listing>,
 }
 
-fn default_reply() -> Option<Listing> {
-    None
+/// Subreddit, kind `t5`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Subreddit {
+    pub id: ThingId,
+    pub name: Fullname,
+    /// Name without prefix, e.g. `rust`
+    pub display_name: String,
+    /// Name with prefix, e.g. `r/rust`
+    #[serde(default)]
+    pub display_name_prefixed: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub public_description: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub subreddit_type: String,
+    pub subscribers: Option<u64>,
+    pub active_user_count: Option<u64>,
+    pub over18: Option<bool>,
+    pub lang: Option<String>,
+    pub icon_img: Option<String>,
+    pub user_is_subscriber: Option<bool>,
+    pub user_is_moderator: Option<bool>,
+    #[serde(default)]
+    pub created: f64,
+    #[serde(default)]
+    pub created_utc: f64,
+}
+
+/// Award, kind `t6`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Award {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub icon_url: String,
+    #[serde(default)]
+    pub coin_price: u32,
+    /// How often it was awarded, if part of a thing's awardings
+    pub count: Option<u32>,
+}
+
+/// Stub for comments left out of a comment tree, kind `more`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct More {
+    /// `_` for "continue this thread" stubs
+    pub id: String,
+    pub name: String,
+    /// Fullname of the link or comment the left out comments reply to
+    pub parent_id: Fullname,
+    /// Number of comments left out
+    #[serde(default)]
+    pub count: usize,
+    #[serde(default)]
+    pub depth: u32,
+    /// IDs of the left out comments
+    #[serde(default)]
+    pub children: Vec<ThingId>,
+}
+
+impl More {
+    /// Whether the stub is a "continue this thread" link. These stand for replies too deep
+    /// to be part of the tree and list no `children`
+    pub fn is_continue_thread(&self) -> bool {
+        self.id == "_"
+    }
+}
+
+/// Custom feed combining several subreddits, kind `LabeledMulti`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LabeledMulti {
+    pub name: String,
+    #[serde(default)]
+    pub display_name: String,
+    /// Path of the feed, e.g. `/user/spez/m/feed`
+    #[serde(default)]
+    pub path: String,
+    #[serde(default)]
+    pub owner: String,
+    #[serde(default)]
+    pub description_md: String,
+    #[serde(default)]
+    pub visibility: String,
+    pub icon_url: Option<String>,
+    #[serde(default)]
+    pub can_edit: bool,
+    #[serde(default)]
+    pub subreddits: Vec<MultiSubreddit>,
+    #[serde(default)]
+    pub created: f64,
+    #[serde(default)]
+    pub created_utc: f64,
+}
+
+/// Subreddit of a `LabeledMulti`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MultiSubreddit {
+    pub name: String,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct LinkFlairRichtext {
     pub e: String,
-    pub t: String,
+    pub t: Option<String>,
+}
+
+/// Deserializes `data` of a thing, keeping the error message of serde_json
+fn from_value<T: DeserializeOwned, E: de::Error>(data: Value) -> Result<T, E> {
+    serde_json::from_value(data).map_err(E::custom)
+}
+
+/// Whether `url` points to an image file
+fn is_image_url(url: &str) -> bool {
+    let path = url
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    [".jpg", ".jpeg", ".png", ".gif", ".webp"]
+        .iter()
+        .any(|extension| path.ends_with(extension))
+}
+
+/// Reddit sends `""` instead of `null` for things without replies
+fn empty_string_as_none<'de, D>(deserializer: D) -> Result<Option<Listing>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::Null => Ok(None),
+        Value::String(ref s) if s.is_empty() => Ok(None),
+        value => from_value(value).map(Some),
+    }
+}
+
+/// Reddit sends `false` for things never edited and the timestamp of the last edit otherwise
+fn false_or_timestamp<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Value::deserialize(deserializer)?.as_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fullname::ThingKind;
+    use super::*;
+
+    #[test]
+    fn test_dispatch_on_kind() {
+        let things: Vec<Thing> = serde_json::from_str(
+            r#"[
+                {"kind": "t1", "data": {"id": "c1", "name": "t1_c1", "link_id": "t3_l1", "parent_id": "t3_l1", "body": "Hi", "replies": "", "edited": false, "ups": 3, "author_fullname": "t2_1w72", "subreddit_id": "t5_2fwo"}},
+                {"kind": "t3", "data": {"id": "l1", "name": "t3_l1", "title": "Rust", "edited": 1595000000.0}},
+                {"kind": "t5", "data": {"id": "2fwo", "name": "t5_2fwo", "display_name": "rust"}},
+                {"kind": "more", "data": {"id": "c2", "name": "t1_c2", "parent_id": "t3_l1", "count": 2, "children": ["c2", "c3"]}},
+                {"kind": "t8", "data": {"id": "x"}}
+            ]"#,
+        )
+        .unwrap();
+        let comment = things[0].as_comment().unwrap();
+        assert_eq!(comment.body, "Hi");
+        assert!(comment.replies.is_none());
+        assert_eq!(comment.edited, None);
+        assert_eq!(comment.ups(), 3);
+        assert_eq!(comment.author_fullname.unwrap().kind(), ThingKind::ACCOUNT);
+        assert_eq!(comment.subreddit_id.unwrap().to_string(), "t5_2fwo");
+        assert_eq!(things[1].as_link().unwrap().edited, Some(1595000000.0));
+        assert_eq!(things[2].kind(), "t5");
+        assert_eq!(things[3].as_more().unwrap().children.len(), 2);
+        match &things[4] {
+            Thing::Unknown { kind, data } => {
+                assert_eq!(kind, "t8");
+                assert_eq!(data["id"], "x");
+            }
+            other => panic!("Unexpected thing {:?}", other),
+        }
+        // Things serialize the way reddit sends them
+        let json = serde_json::to_value(&things).unwrap();
+        assert_eq!(json[0]["kind"], "t1");
+        assert_eq!(json[0]["data"]["body"], "Hi");
+        assert_eq!(
+            json[4],
+            serde_json::json!({"kind": "t8", "data": {"id": "x"}})
+        );
+    }
+
+    #[test]
+    fn test_best_media_url() {
+        let link = |data: &str| -> Link {
+            serde_json::from_str(&format!(r#"{{"id": "l1", "name": "t3_l1", {}}}"#, data)).unwrap()
+        };
+        let video = link(
+            r#""url": "https://v.redd.it/abc", "secure_media": {"reddit_video": {"fallback_url": "https://v.redd.it/abc/DASH_720.mp4", "dash_url": "https://v.redd.it/abc/DASHPlaylist.mpd", "hls_url": "https://v.redd.it/abc/HLSPlaylist.m3u8", "width": 1280, "height": 720, "duration": 12}}"#,
+        );
+        assert_eq!(
+            video.best_media_url().unwrap(),
+            "https://v.redd.it/abc/DASH_720.mp4"
+        );
+        let gallery = link(
+            r#""is_gallery": true, "url": "https://www.reddit.com/gallery/l1", "media_metadata": {
+                "a": {"status": "valid", "e": "Image", "m": "image/jpg", "p": [{"y": 108, "x": 108, "u": "https://preview.redd.it/a.jpg?width=108&amp;s=1"}], "s": {"y": 1000, "x": 1000, "u": "https://preview.redd.it/a.jpg?width=1000&amp;s=2"}},
+                "b": {"status": "valid", "e": "AnimatedImage", "m": "image/gif", "s": {"y": 100, "x": 100, "gif": "https://i.redd.it/b.gif", "mp4": "https://preview.redd.it/b.gif?format=mp4"}}},
+                "gallery_data": {"items": [{"media_id": "b", "id": 2}, {"media_id": "a", "id": 1, "caption": "First"}]}"#,
+        );
+        assert_eq!(
+            gallery.gallery_urls(),
+            vec![
+                "https://preview.redd.it/b.gif?format=mp4",
+                "https://preview.redd.it/a.jpg?width=1000&s=2"
+            ]
+        );
+        let article = link(
+            r#""url": "https://blog.rust-lang.org/", "post_hint": "link", "media_embed": {}, "preview": {"enabled": false, "images": [{"id": "p", "source": {"url": "https://external-preview.redd.it/p.jpg?auto=webp&amp;s=3", "width": 640, "height": 480}, "resolutions": [], "variants": {}}]}"#,
+        );
+        assert_eq!(
+            article.best_media_url().unwrap(),
+            "https://external-preview.redd.it/p.jpg?auto=webp&s=3"
+        );
+        assert_eq!(link(r#""is_self": true"#).best_media_url(), None);
+    }
+
+    #[test]
+    fn test_moderation_fields() {
+        let comment: Comment = serde_json::from_str(
+            r#"{"id": "c1", "name": "t1_c1", "link_id": "t3_l1", "parent_id": "t3_l1",
+                "mod_reports": [["Spam", "automoderator"]], "user_reports": [["Rude", 2, false, true]], "num_reports": 3,
+                "banned_by": true, "removed_by_category": "automod_filtered", "approved_at_utc": null,
+                "all_awardings": [{"id": "gid_1", "name": "Silver", "coin_price": 100, "count": 2}], "gildings": {"gid_1": 2}}"#,
+        )
+        .unwrap();
+        assert_eq!(comment.mod_reports[0][1], "automoderator");
+        assert_eq!(comment.user_reports[0][1], 2);
+        assert_eq!(comment.num_reports, Some(3));
+        assert_eq!(comment.banned_by, Some(Value::Bool(true)));
+        assert_eq!(
+            comment.removed_by_category.as_deref(),
+            Some("automod_filtered")
+        );
+        assert_eq!(comment.all_awardings[0].count, Some(2));
+        assert_eq!(comment.gildings["gid_1"], 2);
+    }
+
+    #[test]
+    fn test_message_without_author() {
+        let thing: Thing = serde_json::from_str(
+            r#"{"kind": "t4", "data": {"id": "m1", "name": "t4_m1", "author": null, "subject": "Welcome", "replies": ""}}"#,
+        )
+        .unwrap();
+        match thing {
+            Thing::Message(message) => {
+                assert_eq!(message.author, None);
+                assert_eq!(message.subject, "Welcome");
+            }
+            other => panic!("Unexpected thing {:?}", other),
+        }
+    }
 }
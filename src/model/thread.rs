@@ -0,0 +1,268 @@
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::slice;
+
+use serde::{Deserialize, Serialize};
+
+use super::super::error::RedditError;
+use super::fullname::Fullname;
+use super::listing::{Listing, ListingCollection};
+use super::thing::{Comment, Link, More, Thing};
+
+/// Submission and its comment tree, as returned by `Reddit::thread_by_permalink`.
+/// Navigation borrows from the tree, nothing is cloned
+///
+/// # Example
+/// ```no_run
+/// use rsreddit::client::Reddit;
+/// let reddit = Reddit::default().build();
+/// let thread = reddit.thread_by_permalink("/r/rust/comments/hwuvmf/").unwrap();
+/// println!("{}", thread.link.title);
+/// for (depth, comment) in thread.depth_first() {
+///     println!("{}{}", "  ".repeat(depth), comment.body);
+/// }
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Thread {
+    /// The submission
+    pub link: Link,
+    /// Top level comments, with their replies nested
+    pub comments: Listing,
+}
+
+impl Thread {
+    /// Top level comments. `more` stubs are skipped
+    pub fn top_level(&self) -> impl Iterator<Item = &Comment> {
+        comments(&self.comments)
+    }
+
+    /// All comments in depth-first order, with their depth. Top level comments have depth `0`
+    pub fn depth_first(&self) -> DepthFirst<'_> {
+        DepthFirst {
+            stack: vec![(0, self.comments.data.children.iter())],
+        }
+    }
+
+    /// All comments in breadth-first order, with their depth. Top level comments have depth `0`
+    pub fn breadth_first(&self) -> BreadthFirst<'_> {
+        BreadthFirst {
+            queue: self.top_level().map(|comment| (0, comment)).collect(),
+        }
+    }
+
+    /// All comments in depth-first order
+    pub fn flatten(&self) -> Vec<&Comment> {
+        self.depth_first().map(|(_, comment)| comment).collect()
+    }
+
+    /// Number of comments in the tree, without the ones left out in `more` stubs
+    pub fn len(&self) -> usize {
+        self.depth_first().count()
+    }
+
+    /// Whether the tree holds no comments
+    pub fn is_empty(&self) -> bool {
+        self.top_level().next().is_none()
+    }
+
+    /// Comment with fullname `fullname`
+    pub fn find(&self, fullname: &Fullname) -> Option<&Comment> {
+        self.depth_first()
+            .map(|(_, comment)| comment)
+            .find(|comment| comment.name == *fullname)
+    }
+
+    /// Comment the comment `fullname` replies to. `None` for top level comments
+    pub fn parent(&self, fullname: &Fullname) -> Option<&Comment> {
+        let mut path = self.path(fullname)?;
+        path.pop();
+        path.pop()
+    }
+
+    /// Comments above the comment `fullname`, starting with its parent and ending with a top level comment
+    pub fn ancestors(&self, fullname: &Fullname) -> Vec<&Comment> {
+        let mut path = self.path(fullname).unwrap_or_default();
+        path.pop();
+        path.reverse();
+        path
+    }
+
+    /// Comments from a top level comment down to the comment `fullname`, found in one traversal
+    fn path(&self, fullname: &Fullname) -> Option<Vec<&Comment>> {
+        let mut path = Vec::new();
+        for (depth, comment) in self.depth_first() {
+            path.truncate(depth);
+            path.push(comment);
+            if comment.name == *fullname {
+                return Some(path);
+            }
+        }
+        None
+    }
+
+    /// Number of replies below the comment `fullname`, at any depth. `None` if there is no such comment
+    pub fn subtree_size(&self, fullname: &Fullname) -> Option<usize> {
+        self.find(fullname).map(|comment| match &comment.replies {
+            Some(replies) => DepthFirst {
+                stack: vec![(0, replies.data.children.iter())],
+            }
+            .count(),
+            None => 0,
+        })
+    }
+
+    /// `more` stubs left in the tree. See `Reddit::expand_thread`
+    pub fn more_stubs(&self) -> Vec<&More> {
+        self.comments.more_stubs()
+    }
+}
+
+impl TryFrom<ListingCollection> for Thread {
+    type Error = RedditError;
+
+    /// Splits the two listings of a thread response into submission and comments
+    fn try_from(collection: ListingCollection) -> Result<Thread, RedditError> {
+        let mut listings = collection.listings.into_iter();
+        let link_listing = listings.next().ok_or_else(|| {
+            RedditError::UnexpectedResponse("Thread response has no link listing".to_owned())
+        })?;
+        let link = link_listing
+            .data
+            .children
+            .into_iter()
+            .find_map(|thing| match thing {
+                Thing::Link(link) => Some(link),
+                _ => None,
+            })
+            .ok_or_else(|| {
+                RedditError::UnexpectedResponse(
+                    "Link listing of thread response holds no link".to_owned(),
+                )
+            })?;
+        let comments = listings
+            .next()
+            .unwrap_or_else(|| Listing::from_children(Vec::new()));
+        Ok(Thread { link, comments })
+    }
+}
+
+/// Comments of `listing` without their replies
+fn comments(listing: &Listing) -> impl Iterator<Item = &Comment> {
+    listing.data.children.iter().filter_map(Thing::as_comment)
+}
+
+/// Depth-first iterator over comments of a `Thread`, see `Thread::depth_first`
+pub struct DepthFirst<'a> {
+    stack: Vec<(usize, slice::Iter<'a, Thing>)>,
+}
+
+impl<'a> Iterator for DepthFirst<'a> {
+    type Item = (usize, &'a Comment);
+
+    fn next(&mut self) -> Option<(usize, &'a Comment)> {
+        loop {
+            let (depth, children) = self.stack.last_mut()?;
+            let depth = *depth;
+            match children.next() {
+                Some(Thing::Comment(comment)) => {
+                    if let Some(replies) = &comment.replies {
+                        self.stack.push((depth + 1, replies.data.children.iter()));
+                    }
+                    return Some((depth, comment));
+                }
+                Some(_) => {}
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+/// Breadth-first iterator over comments of a `Thread`, see `Thread::breadth_first`
+pub struct BreadthFirst<'a> {
+    queue: VecDeque<(usize, &'a Comment)>,
+}
+
+impl<'a> Iterator for BreadthFirst<'a> {
+    type Item = (usize, &'a Comment);
+
+    fn next(&mut self) -> Option<(usize, &'a Comment)> {
+        let (depth, comment) = self.queue.pop_front()?;
+        if let Some(replies) = &comment.replies {
+            self.queue
+                .extend(comments(replies).map(|reply| (depth + 1, reply)));
+        }
+        Some((depth, comment))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::test_fixtures::{comment_json, thread_json};
+    use super::*;
+
+    // a
+    // ├ b
+    // │ └ d
+    // └ c
+    // e
+    fn thread() -> Thread {
+        let d = comment_json("d", "t1_b", &[]);
+        let b = comment_json("b", "t1_a", &[d]);
+        let c = comment_json("c", "t1_a", &[]);
+        let a = comment_json("a", "t3_l1", &[b, c]);
+        let e = comment_json("e", "t3_l1", &[]);
+        let json = format!(r#"{{"listings": {}}}"#, thread_json(&[a, e]));
+        let collection: ListingCollection = serde_json::from_str(&json).unwrap();
+        Thread::try_from(collection).unwrap()
+    }
+
+    fn bodies<'a>(comments: impl Iterator<Item = (usize, &'a Comment)>) -> Vec<(usize, &'a str)> {
+        comments
+            .map(|(depth, comment)| (depth, comment.body.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_traversal_order() {
+        let thread = thread();
+        assert_eq!(thread.link.title, "Thread");
+        assert_eq!(
+            bodies(thread.depth_first()),
+            vec![(0, "a"), (1, "b"), (2, "d"), (1, "c"), (0, "e")]
+        );
+        assert_eq!(
+            bodies(thread.breadth_first()),
+            vec![(0, "a"), (0, "e"), (1, "b"), (1, "c"), (2, "d")]
+        );
+        assert_eq!(thread.len(), 5);
+    }
+
+    #[test]
+    fn test_lookup() {
+        let thread = thread();
+        let d: Fullname = "t1_d".parse().unwrap();
+        assert_eq!(thread.parent(&d).unwrap().body, "b");
+        let ancestors: Vec<&str> = thread
+            .ancestors(&d)
+            .iter()
+            .map(|comment| comment.body.as_str())
+            .collect();
+        assert_eq!(ancestors, vec!["b", "a"]);
+        assert!(thread.parent(&"t1_a".parse().unwrap()).is_none());
+        assert_eq!(thread.subtree_size(&"t1_a".parse().unwrap()), Some(3));
+        assert_eq!(thread.subtree_size(&"t1_zz".parse().unwrap()), None);
+        assert!(thread.ancestors(&"t1_zz".parse().unwrap()).is_empty());
+        assert_eq!(thread.parent(&"t1_c".parse().unwrap()).unwrap().body, "a");
+    }
+
+    #[test]
+    fn test_missing_link_listing() {
+        let collection: ListingCollection = serde_json::from_str(r#"{"listings": []}"#).unwrap();
+        match Thread::try_from(collection) {
+            Err(RedditError::UnexpectedResponse(message)) => assert!(message.contains("link")),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
@@ -1,6 +1,16 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use super::super::oauth2::RedditApiScope;
+use super::super::scope::ScopeSet;
+use super::super::util::{unix_timestamp, REDACTED};
+
+/// Tokens are refreshed this many seconds before they actually expire,
+/// so they do not run out while a request is on its way
+const EXPIRY_MARGIN: u64 = 60;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct OAuthToken {
     pub access_token: String,
     pub token_type: String,
@@ -8,8 +18,117 @@ pub struct OAuthToken {
     pub scope: String,
     #[serde(default = "empty_string")]
     pub refresh_token: String,
+    /// Unix timestamp the token was issued at.
+    /// Not part of reddit's response, so it is set once the token was received.
+    /// Tokens saved without it count as expired
+    #[serde(default)]
+    pub created_at: u64,
+}
+
+impl OAuthToken {
+    /// Unix timestamp the token expires at
+    pub fn expires_at(&self) -> u64 {
+        self.created_at + self.expires_in as u64
+    }
+    /// Whether the token expired or is about to expire
+    pub fn is_expired(&self) -> bool {
+        self.expires_at() <= unix_timestamp() + EXPIRY_MARGIN
+    }
+    /// Scopes the token was granted
+    pub fn scopes(&self) -> ScopeSet {
+        ScopeSet::parse(&self.scope)
+    }
+    /// Whether the token was granted `scope`. Application-only tokens are granted all scopes ( `*` )
+    pub fn has_scope(&self, scope: RedditApiScope) -> bool {
+        self.scopes().contains(scope)
+    }
+    /// Whether a new access token can be requested with `refresh_token`
+    pub fn is_refreshable(&self) -> bool {
+        !self.refresh_token.is_empty()
+    }
+}
+
+impl fmt::Debug for OAuthToken {
+    /// Access and refresh token are redacted, so tokens can be logged safely
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OAuthToken")
+            .field("access_token", &REDACTED)
+            .field("token_type", &self.token_type)
+            .field("expires_in", &self.expires_in)
+            .field("scope", &self.scope)
+            .field(
+                "refresh_token",
+                &if self.is_refreshable() { REDACTED } else { "" },
+            )
+            .field("created_at", &self.created_at)
+            .finish()
+    }
+}
+
+/// What a token grants, as reported by reddit
+#[derive(Debug, Clone)]
+pub struct TokenInfo {
+    /// Name of the account the token acts for.
+    /// `None` for application-only tokens and tokens without `identity` scope
+    pub username: Option<String>,
+    /// Scopes the token was granted
+    pub scopes: ScopeSet,
+    /// Unix timestamp the token expires at
+    pub expires_at: u64,
 }
 
 fn empty_string() -> String {
     "".to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_created_at_counts_as_expired() {
+        let token: OAuthToken = serde_json::from_str(
+            r#"{"access_token": "abc", "token_type": "bearer", "expires_in": 3600, "scope": "read"}"#,
+        )
+        .unwrap();
+        assert_eq!(token.created_at, 0);
+        assert!(token.is_expired());
+        assert!(!token.is_refreshable());
+    }
+
+    #[test]
+    fn test_debug_redacts_tokens() {
+        let token: OAuthToken = serde_json::from_str(
+            r#"{"access_token": "secret-access", "token_type": "bearer", "expires_in": 3600, "scope": "read", "refresh_token": "secret-refresh"}"#,
+        )
+        .unwrap();
+        let debug = format!("{:?}", token);
+        assert!(!debug.contains("secret"));
+        assert!(debug.contains("read"));
+    }
+
+    #[test]
+    fn test_has_scope() {
+        let mut token: OAuthToken = serde_json::from_str(
+            r#"{"access_token": "abc", "token_type": "bearer", "expires_in": 3600, "scope": "wikiread submit"}"#,
+        )
+        .unwrap();
+        assert!(token.has_scope(RedditApiScope::submit));
+        assert!(!token.has_scope(RedditApiScope::read));
+        token.scope = "*".to_owned();
+        assert!(token.has_scope(RedditApiScope::modconfig));
+    }
+
+    #[test]
+    fn test_is_expired() {
+        let token = OAuthToken {
+            access_token: "abc".to_owned(),
+            token_type: "bearer".to_owned(),
+            expires_in: 3600,
+            scope: "read".to_owned(),
+            refresh_token: "refresh".to_owned(),
+            created_at: unix_timestamp() - 3590,
+        };
+        assert!(token.is_expired());
+    }
+}
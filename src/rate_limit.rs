@@ -0,0 +1,174 @@
+//! Client side rate limiting based on the `X-Ratelimit-*` headers reddit sends with every response
+//! See https://github.com/reddit-archive/reddit/wiki/API#rules
+
+//Built in libraries
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+//Third party libraries
+use serde::{Deserialize, Serialize};
+
+//Own stuff
+use super::error::RedditError;
+use super::transport::HttpResponse;
+
+/// Determines what happens when the request quota of the current window is used up
+#[derive(PartialEq, Clone, Copy, Serialize, Deserialize, Debug)]
+pub enum RateLimitMode {
+    /// Block until the window resets, then send the request
+    SLEEP,
+    /// Return `RedditError::RateLimited` instead of sending the request
+    ERROR,
+}
+
+#[derive(Debug, Default)]
+struct RateLimitState {
+    // Requests left in the current window
+    remaining: Option<f64>,
+    // Point in time the current window resets
+    reset_at: Option<Instant>,
+}
+
+/// Tracks the quota reported by reddit and holds requests back before it is exceeded
+#[derive(Debug)]
+pub struct RateLimiter {
+    mode: RateLimitMode,
+    state: Mutex<RateLimitState>,
+}
+
+impl RateLimiter {
+    pub fn new(mode: RateLimitMode) -> RateLimiter {
+        RateLimiter {
+            mode,
+            state: Mutex::new(RateLimitState::default()),
+        }
+    }
+
+    pub fn mode(&self) -> RateLimitMode {
+        self.mode
+    }
+
+    /// Call before sending a request.
+    /// If the quota is used up, either sleeps until the window resets or returns `RedditError::RateLimited`
+    pub fn acquire(&self) -> Result<(), RedditError> {
+        // Sleep without the lock, so responses in flight can still update the quota
+        while let Some(wait) = self.try_acquire()? {
+            debug!("Rate limit reached. Sleeping for {:?}", wait);
+            thread::sleep(wait);
+        }
+        Ok(())
+    }
+
+    /// Counts a request against the quota if it is not used up.
+    /// Otherwise returns the time until the window resets in `RateLimitMode::SLEEP`,
+    /// after which the caller tries again, or `RedditError::RateLimited` in `RateLimitMode::ERROR`
+    pub(crate) fn try_acquire(&self) -> Result<Option<Duration>, RedditError> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        if let Some(reset_at) = state.reset_at {
+            if reset_at <= now {
+                // New window. Quota is unknown until the next response arrives
+                *state = RateLimitState::default();
+            } else if state.remaining.is_some_and(|r| r < 1.0) {
+                let wait = reset_at - now;
+                return match self.mode {
+                    RateLimitMode::ERROR => Err(RedditError::RateLimited {
+                        retry_after: wait.as_secs() + 1,
+                    }),
+                    RateLimitMode::SLEEP => Ok(Some(wait)),
+                };
+            }
+        }
+        // Count request against the quota, so concurrent requests do not overshoot it
+        if let Some(remaining) = state.remaining.as_mut() {
+            *remaining -= 1.0;
+        }
+        Ok(None)
+    }
+
+    /// Updates quota with the `X-Ratelimit-Remaining` and `X-Ratelimit-Reset` headers of `response`
+    pub fn update(&self, response: &HttpResponse) {
+        let remaining = response
+            .header("x-ratelimit-remaining")
+            .and_then(|r| r.parse::<f64>().ok());
+        let reset = response
+            .header("x-ratelimit-reset")
+            .and_then(|r| r.parse::<u64>().ok());
+        let mut state = self.state.lock().unwrap();
+        if let (Some(remaining), Some(reset)) = (remaining, reset) {
+            state.remaining = Some(remaining);
+            state.reset_at = Some(Instant::now() + Duration::from_secs(reset));
+        } else if response.status == 429 {
+            // Too many requests without quota headers. Hold back until `Retry-After` passed
+            let retry_after = response
+                .header("retry-after")
+                .and_then(|r| r.parse::<u64>().ok())
+                .unwrap_or(60);
+            state.remaining = Some(0.0);
+            state.reset_at = Some(Instant::now() + Duration::from_secs(retry_after));
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> RateLimiter {
+        RateLimiter::new(RateLimitMode::SLEEP)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(remaining: &str, reset: &str) -> HttpResponse {
+        let mut response = HttpResponse::new(200, "");
+        response
+            .headers
+            .insert("x-ratelimit-remaining".to_owned(), remaining.to_owned());
+        response
+            .headers
+            .insert("x-ratelimit-reset".to_owned(), reset.to_owned());
+        response
+    }
+
+    #[test]
+    fn test_acquire_without_quota_information() {
+        let limiter = RateLimiter::new(RateLimitMode::ERROR);
+        assert!(limiter.acquire().is_ok());
+    }
+
+    #[test]
+    fn test_acquire_errors_when_quota_used_up() {
+        let limiter = RateLimiter::new(RateLimitMode::ERROR);
+        limiter.update(&response("2.0", "120"));
+        assert!(limiter.acquire().is_ok());
+        assert!(limiter.acquire().is_ok());
+        match limiter.acquire() {
+            Err(RedditError::RateLimited { retry_after }) => assert!(retry_after <= 121),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_acquire_after_window_reset() {
+        let limiter = RateLimiter::new(RateLimitMode::ERROR);
+        limiter.update(&response("0.0", "0"));
+        assert!(limiter.acquire().is_ok());
+    }
+
+    #[test]
+    fn test_sleep_does_not_hold_lock() {
+        let limiter = std::sync::Arc::new(RateLimiter::new(RateLimitMode::SLEEP));
+        limiter.update(&response("0.0", "1"));
+        let sleeper = {
+            let limiter = limiter.clone();
+            thread::spawn(move || limiter.acquire())
+        };
+        thread::sleep(Duration::from_millis(100));
+        let start = Instant::now();
+        limiter.update(&response("10.0", "600"));
+        assert!(start.elapsed() < Duration::from_millis(500));
+        assert!(sleeper.join().unwrap().is_ok());
+    }
+}
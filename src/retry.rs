@@ -0,0 +1,169 @@
+//! Retry policy for requests failing with `429 Too Many Requests`, server errors or transport errors
+
+//Built in libraries
+use std::time::Duration;
+
+//Third party libraries
+use rand::{self, Rng};
+use serde::{Deserialize, Serialize};
+
+//Own stuff
+use super::error::RedditError;
+use super::transport::{HttpMethod, HttpRequest, HttpResponse};
+
+/// Determines how often and how long to wait before a failed request is sent again.
+/// Delay doubles with every attempt, starting at `base_delay`, and is capped at `max_delay`
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use rsreddit::retry::RetryPolicy;
+/// let policy = RetryPolicy::default()
+///                      .max_attempts(5)
+///                      .base_delay(Duration::from_millis(200))
+///                      .retry_post(true);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Number of attempts including the first one. `1` disables retries
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Randomizes delay between half and the full computed delay
+    pub jitter: bool,
+    /// POST requests are not idempotent, e.g. a comment could be submitted twice.
+    /// Thus, they are only retried if set
+    pub retry_post: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+            retry_post: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy which never retries
+    pub fn none() -> RetryPolicy {
+        RetryPolicy::default().max_attempts(1)
+    }
+    /// Set `max_attempts`
+    pub fn max_attempts(mut self, max_attempts: u32) -> RetryPolicy {
+        self.max_attempts = max_attempts;
+        self
+    }
+    /// Set `base_delay`
+    pub fn base_delay(mut self, base_delay: Duration) -> RetryPolicy {
+        self.base_delay = base_delay;
+        self
+    }
+    /// Set `max_delay`
+    pub fn max_delay(mut self, max_delay: Duration) -> RetryPolicy {
+        self.max_delay = max_delay;
+        self
+    }
+    /// Set `jitter`
+    pub fn jitter(mut self, jitter: bool) -> RetryPolicy {
+        self.jitter = jitter;
+        self
+    }
+    /// Set `retry_post`
+    pub fn retry_post(mut self, retry_post: bool) -> RetryPolicy {
+        self.retry_post = retry_post;
+        self
+    }
+
+    /// Whether `request` should be sent again after `attempt` attempts ended in `result`
+    pub fn should_retry(
+        &self,
+        request: &HttpRequest,
+        attempt: u32,
+        result: &Result<HttpResponse, RedditError>,
+    ) -> bool {
+        if attempt >= self.max_attempts {
+            return false;
+        }
+        if request.method == HttpMethod::POST && !self.retry_post {
+            return false;
+        }
+        match result {
+            Ok(response) => is_retryable_status(response.status),
+            Err(RedditError::Transport(_)) => true,
+            Err(_) => false,
+        }
+    }
+
+    /// Time to wait after `attempt` failed attempts.
+    /// A `Retry-After` header of `response` is respected if it asks for a longer delay
+    pub fn delay(&self, attempt: u32, response: Option<&HttpResponse>) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let mut delay = self
+            .base_delay
+            .checked_mul(factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+        if self.jitter {
+            let half = delay / 2;
+            let spread = (delay - half).as_millis() as u64;
+            delay = half + Duration::from_millis(rand::thread_rng().gen_range(0, spread + 1));
+        }
+        let retry_after = response
+            .and_then(|r| r.header("retry-after"))
+            .and_then(|r| r.parse::<u64>().ok())
+            .map(Duration::from_secs);
+        match retry_after {
+            Some(retry_after) if retry_after > delay => retry_after,
+            _ => delay,
+        }
+    }
+}
+
+/// `429 Too Many Requests` and server errors are worth another try
+fn is_retryable_status(status: u32) -> bool {
+    status == 429 || status == 500 || status == 502 || status == 503 || status == 504
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_retry() {
+        let policy = RetryPolicy::default();
+        let get = HttpRequest::get("https://oauth.reddit.com/hot");
+        let post = HttpRequest::post("https://oauth.reddit.com/api/comment", "");
+        let unavailable = Ok(HttpResponse::new(503, ""));
+        assert!(policy.should_retry(&get, 1, &unavailable));
+        assert!(!policy.should_retry(&get, 3, &unavailable));
+        assert!(!policy.should_retry(&get, 1, &Ok(HttpResponse::new(404, ""))));
+        assert!(!policy.should_retry(&post, 1, &unavailable));
+        assert!(policy.retry_post(true).should_retry(&post, 1, &unavailable));
+    }
+
+    #[test]
+    fn test_delay_doubles_and_is_capped() {
+        let policy = RetryPolicy::default()
+            .jitter(false)
+            .base_delay(Duration::from_secs(1))
+            .max_delay(Duration::from_secs(3));
+        assert_eq!(policy.delay(1, None), Duration::from_secs(1));
+        assert_eq!(policy.delay(2, None), Duration::from_secs(2));
+        assert_eq!(policy.delay(3, None), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_delay_respects_retry_after() {
+        let policy = RetryPolicy::default().jitter(false);
+        let mut response = HttpResponse::new(429, "");
+        response
+            .headers
+            .insert("retry-after".to_owned(), "10".to_owned());
+        assert_eq!(policy.delay(1, Some(&response)), Duration::from_secs(10));
+    }
+}
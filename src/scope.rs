@@ -0,0 +1,124 @@
+//! Set of OAuth2 scopes a token is requested with or was granted
+
+//Built in libraries
+use std::collections::BTreeSet;
+use std::fmt;
+use std::iter::FromIterator;
+
+//Own stuff
+use super::oauth2::RedditApiScope;
+
+/// Set of `RedditApiScope`s.
+/// `RedditApiScope::all` ( `*` ) grants every scope, as it is the case for script apps and application-only tokens
+///
+/// # Example
+/// ```
+/// use rsreddit::oauth2::RedditApiScope;
+/// use rsreddit::scope::ScopeSet;
+///
+/// let scopes = ScopeSet::from(vec![RedditApiScope::read, RedditApiScope::submit]);
+/// assert_eq!(scopes.to_string(), "read submit");
+/// assert!(ScopeSet::parse("wikiread").contains(RedditApiScope::wikiread));
+/// assert!(!ScopeSet::parse("wikiread").contains(RedditApiScope::read));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScopeSet {
+    scopes: BTreeSet<RedditApiScope>,
+}
+
+impl ScopeSet {
+    /// Empty set
+    pub fn new() -> ScopeSet {
+        ScopeSet::default()
+    }
+
+    /// Set granting every scope
+    pub fn all() -> ScopeSet {
+        ScopeSet::new().with(RedditApiScope::all)
+    }
+
+    /// Parses scopes of a token, separated by spaces or commas.
+    /// Scopes unknown to this crate are skipped
+    pub fn parse(scope_string: &str) -> ScopeSet {
+        scope_string
+            .split([' ', ','])
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| match s.parse::<RedditApiScope>() {
+                Ok(scope) => Some(scope),
+                Err(_) => {
+                    debug!("Skipping unknown scope `{}`", s);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Adds `scope`
+    pub fn with(mut self, scope: RedditApiScope) -> ScopeSet {
+        self.scopes.insert(scope);
+        self
+    }
+
+    /// Whether `scope` is granted, either explicitly or by `RedditApiScope::all`
+    pub fn contains(&self, scope: RedditApiScope) -> bool {
+        self.scopes.contains(&scope) || self.scopes.contains(&RedditApiScope::all)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scopes.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RedditApiScope> {
+        self.scopes.iter()
+    }
+}
+
+impl fmt::Display for ScopeSet {
+    /// Space separated scopes, as expected by the `scope` parameter of reddit
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let scopes: Vec<String> = self.scopes.iter().map(RedditApiScope::to_string).collect();
+        write!(f, "{}", scopes.join(" "))
+    }
+}
+
+impl FromIterator<RedditApiScope> for ScopeSet {
+    fn from_iter<I: IntoIterator<Item = RedditApiScope>>(iter: I) -> ScopeSet {
+        ScopeSet {
+            scopes: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl From<Vec<RedditApiScope>> for ScopeSet {
+    fn from(scopes: Vec<RedditApiScope>) -> ScopeSet {
+        scopes.into_iter().collect()
+    }
+}
+
+impl From<RedditApiScope> for ScopeSet {
+    fn from(scope: RedditApiScope) -> ScopeSet {
+        ScopeSet::new().with(scope)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_scope_set() {
+        let scopes = ScopeSet::parse("read,submit modmail unknown");
+        assert!(scopes.contains(RedditApiScope::read));
+        assert!(scopes.contains(RedditApiScope::modmail));
+        assert!(!scopes.contains(RedditApiScope::wikiread));
+        assert_eq!(scopes.iter().count(), 3);
+    }
+
+    #[test]
+    fn test_all_scopes() {
+        let scopes = ScopeSet::parse("*");
+        assert_eq!(scopes, ScopeSet::all());
+        assert!(scopes.contains(RedditApiScope::livemanage));
+        assert_eq!(scopes.to_string(), "*");
+    }
+}
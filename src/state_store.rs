@@ -0,0 +1,78 @@
+//! Storage of the `state` strings of pending authorizations.
+//! Web apps issue one state per authorization URL and validate it once reddit redirects back,
+//! possibly in another process. Implement `StateStore` on top of a shared database for that case
+
+//Built in libraries
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+//Own stuff
+use super::error::RedditError;
+
+/// Remembers issued states until their callback arrives
+pub trait StateStore: Debug + Send + Sync {
+    /// Remembers `state`, which was sent with an authorization URL
+    fn insert(&self, state: &str) -> Result<(), RedditError>;
+    /// Removes `state` and returns whether it was issued and is still valid.
+    /// A state is only accepted once, so a callback cannot be replayed
+    fn take(&self, state: &str) -> Result<bool, RedditError>;
+}
+
+/// Keeps states in memory of the current process. States expire after `max_age`
+#[derive(Debug)]
+pub struct MemoryStateStore {
+    states: Mutex<HashMap<String, Instant>>,
+    max_age: Duration,
+}
+
+impl MemoryStateStore {
+    /// Creates store accepting states for `max_age` after they were issued
+    pub fn new(max_age: Duration) -> MemoryStateStore {
+        MemoryStateStore {
+            states: Mutex::new(HashMap::new()),
+            max_age,
+        }
+    }
+}
+
+impl Default for MemoryStateStore {
+    /// States expire after 10 minutes
+    fn default() -> MemoryStateStore {
+        MemoryStateStore::new(Duration::from_secs(600))
+    }
+}
+
+impl StateStore for MemoryStateStore {
+    fn insert(&self, state: &str) -> Result<(), RedditError> {
+        let mut states = self.states.lock().unwrap();
+        // Forget states of abandoned authorizations
+        let max_age = self.max_age;
+        states.retain(|_, issued| issued.elapsed() < max_age);
+        states.insert(state.to_owned(), Instant::now());
+        Ok(())
+    }
+
+    fn take(&self, state: &str) -> Result<bool, RedditError> {
+        let issued = self.states.lock().unwrap().remove(state);
+        Ok(issued.is_some_and(|issued| issued.elapsed() < self.max_age))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_state_store() {
+        let store = MemoryStateStore::default();
+        store.insert("abc").unwrap();
+        assert!(!store.take("xyz").unwrap());
+        assert!(store.take("abc").unwrap());
+        assert!(!store.take("abc").unwrap());
+        let expired = MemoryStateStore::new(Duration::from_secs(0));
+        expired.insert("abc").unwrap();
+        assert!(!expired.take("abc").unwrap());
+    }
+}
@@ -0,0 +1,53 @@
+//! JSON responses and tokens shared by the tests of several modules
+
+//Own stuff
+use super::model::token::OAuthToken;
+use super::util::unix_timestamp;
+
+/// Fresh token granted `scope`, without refresh token
+pub fn token(scope: &str) -> OAuthToken {
+    OAuthToken {
+        access_token: "access".to_owned(),
+        token_type: "bearer".to_owned(),
+        expires_in: 3600,
+        scope: scope.to_owned(),
+        refresh_token: "".to_owned(),
+        created_at: unix_timestamp(),
+    }
+}
+
+/// Listing of `children` without pagination
+pub fn listing_json(children: &[String]) -> String {
+    format!(
+        r#"{{"kind": "Listing", "data": {{"modhash": "", "dist": null, "before": null, "after": null, "children": [{}]}}}}"#,
+        children.join(",")
+    )
+}
+
+/// Link `t3_l1`, which the comments of the fixtures belong to
+pub fn link_json() -> String {
+    r#"{"kind": "t3", "data": {"id": "l1", "name": "t3_l1", "title": "Thread"}}"#.to_owned()
+}
+
+/// Comment `t1_<id>` with body `<id>`, replying to `parent`.
+/// Without `replies`, reddit sends `""` instead of a listing
+pub fn comment_json(id: &str, parent: &str, replies: &[String]) -> String {
+    let replies = if replies.is_empty() {
+        r#""""#.to_owned()
+    } else {
+        listing_json(replies)
+    };
+    format!(
+        r#"{{"kind": "t1", "data": {{"id": "{}", "name": "t1_{}", "link_id": "t3_l1", "parent_id": "{}", "body": "{}", "replies": {}}}}}"#,
+        id, id, parent, id, replies
+    )
+}
+
+/// Thread response of link `t3_l1` with the top level `comments`
+pub fn thread_json(comments: &[String]) -> String {
+    format!(
+        "[{}, {}]",
+        listing_json(&[link_json()]),
+        listing_json(comments)
+    )
+}
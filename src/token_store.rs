@@ -0,0 +1,223 @@
+//! Persistence of `OAuthToken`s across runs of an application.
+//! Tokens are keyed by the client id of the reddit app and an account name chosen by the application
+
+//Built in libraries
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+//Own stuff
+use super::error::RedditError;
+use super::model::token::OAuthToken;
+
+/// Account name used if the application does not set one
+pub const DEFAULT_ACCOUNT: &str = "default";
+
+/// Loads, saves and clears tokens of an account
+pub trait TokenStore: Debug + Send + Sync {
+    /// Token stored for `account` of app `client_id`, if there is one
+    fn load(&self, client_id: &str, account: &str) -> Result<Option<OAuthToken>, RedditError>;
+    /// Stores `token`, replacing the previous token of `account`
+    fn save(&self, client_id: &str, account: &str, token: &OAuthToken) -> Result<(), RedditError>;
+    /// Removes the token of `account`
+    fn clear(&self, client_id: &str, account: &str) -> Result<(), RedditError>;
+}
+
+/// Key of a token in a store
+fn token_key(client_id: &str, account: &str) -> String {
+    format!("{}:{}", client_id, account)
+}
+
+/// Keeps tokens in memory. Tokens are lost once the store is dropped
+#[derive(Debug, Default)]
+pub struct MemoryTokenStore {
+    tokens: Mutex<HashMap<String, OAuthToken>>,
+}
+
+impl TokenStore for MemoryTokenStore {
+    fn load(&self, client_id: &str, account: &str) -> Result<Option<OAuthToken>, RedditError> {
+        let tokens = self.tokens.lock().unwrap();
+        Ok(tokens.get(&token_key(client_id, account)).cloned())
+    }
+
+    fn save(&self, client_id: &str, account: &str, token: &OAuthToken) -> Result<(), RedditError> {
+        let mut tokens = self.tokens.lock().unwrap();
+        tokens.insert(token_key(client_id, account), token.clone());
+        Ok(())
+    }
+
+    fn clear(&self, client_id: &str, account: &str) -> Result<(), RedditError> {
+        self.tokens
+            .lock()
+            .unwrap()
+            .remove(&token_key(client_id, account));
+        Ok(())
+    }
+}
+
+/// Keeps tokens of all accounts in one JSON file.
+/// On unix, the file is only readable and writable by its owner, as tokens grant access to the account
+///
+/// # Example
+/// ```no_run
+/// use std::sync::Arc;
+/// use rsreddit::client::Reddit;
+/// use rsreddit::token_store::FileTokenStore;
+///
+/// let reddit = Reddit::default()
+///                      .token_store(Arc::new(FileTokenStore::new("tokens.json")))
+///                      .account("my_username")
+///                      .build();
+/// ```
+#[derive(Debug)]
+pub struct FileTokenStore {
+    path: PathBuf,
+    // Serializes read-modify-write cycles of the file within this process
+    lock: Mutex<()>,
+}
+
+impl FileTokenStore {
+    /// Creates store writing to `path`. The file is created once the first token is saved
+    pub fn new<P: AsRef<Path>>(path: P) -> FileTokenStore {
+        FileTokenStore {
+            path: path.as_ref().to_path_buf(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Path of the token file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn read(&self) -> Result<BTreeMap<String, OAuthToken>, RedditError> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let content = fs::read_to_string(&self.path).map_err(store_error)?;
+        serde_json::from_str(&content).map_err(store_error)
+    }
+
+    /// Writes `tokens` to a temporary file first, so a crash does not leave a half written file behind
+    fn write(&self, tokens: &BTreeMap<String, OAuthToken>) -> Result<(), RedditError> {
+        if let Some(parent) = self.path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent).map_err(store_error)?;
+            }
+        }
+        let content = serde_json::to_string_pretty(tokens).map_err(store_error)?;
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(".tmp");
+        let mut file = open_private(Path::new(&temp_path)).map_err(store_error)?;
+        file.write_all(content.as_bytes()).map_err(store_error)?;
+        file.sync_all().map_err(store_error)?;
+        fs::rename(&temp_path, &self.path).map_err(store_error)
+    }
+}
+
+impl TokenStore for FileTokenStore {
+    fn load(&self, client_id: &str, account: &str) -> Result<Option<OAuthToken>, RedditError> {
+        let _guard = self.lock.lock().unwrap();
+        Ok(self.read()?.remove(&token_key(client_id, account)))
+    }
+
+    fn save(&self, client_id: &str, account: &str, token: &OAuthToken) -> Result<(), RedditError> {
+        let _guard = self.lock.lock().unwrap();
+        let mut tokens = self.read()?;
+        tokens.insert(token_key(client_id, account), token.clone());
+        self.write(&tokens)
+    }
+
+    fn clear(&self, client_id: &str, account: &str) -> Result<(), RedditError> {
+        let _guard = self.lock.lock().unwrap();
+        let mut tokens = self.read()?;
+        if tokens.remove(&token_key(client_id, account)).is_some() {
+            self.write(&tokens)?;
+        }
+        Ok(())
+    }
+}
+
+/// Creates or truncates `path` with permissions `0600`
+#[cfg(unix)]
+fn open_private(path: &Path) -> std::io::Result<fs::File> {
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+    let file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    // `mode` only applies to newly created files
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    Ok(file)
+}
+
+/// Creates or truncates `path`
+#[cfg(not(unix))]
+fn open_private(path: &Path) -> std::io::Result<fs::File> {
+    fs::File::create(path)
+}
+
+fn store_error<E: std::fmt::Display>(error: E) -> RedditError {
+    RedditError::TokenStore(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(access_token: &str) -> OAuthToken {
+        OAuthToken {
+            access_token: access_token.to_owned(),
+            token_type: "bearer".to_owned(),
+            expires_in: 3600,
+            scope: "read".to_owned(),
+            refresh_token: "refresh".to_owned(),
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_memory_store() {
+        let store = MemoryTokenStore::default();
+        store.save("app", "alice", &token("a")).unwrap();
+        assert_eq!(
+            store.load("app", "alice").unwrap().unwrap().access_token,
+            "a"
+        );
+        assert!(store.load("app", "bob").unwrap().is_none());
+        store.clear("app", "alice").unwrap();
+        assert!(store.load("app", "alice").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_file_store() {
+        let path = std::env::temp_dir().join(format!(
+            "rsreddit_tokens_{}.json",
+            crate::util::generate_random_string(8)
+        ));
+        let store = FileTokenStore::new(&path);
+        assert!(store.load("app", "alice").unwrap().is_none());
+        store.save("app", "alice", &token("a")).unwrap();
+        store.save("app", "bob", &token("b")).unwrap();
+        let reopened = FileTokenStore::new(&path);
+        assert_eq!(
+            reopened.load("app", "alice").unwrap().unwrap().access_token,
+            "a"
+        );
+        reopened.clear("app", "alice").unwrap();
+        assert!(store.load("app", "alice").unwrap().is_none());
+        assert_eq!(store.load("app", "bob").unwrap().unwrap().access_token, "b");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
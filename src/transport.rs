@@ -0,0 +1,363 @@
+//! HTTP transports used by `Reddit` and `RedditOAuth` to talk to the reddit API.
+//! `CurlTransport` is used by default, `MockTransport` answers with canned responses
+//! and allows to test code built on this crate without hitting reddit.com
+
+//Built in libraries
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+//Own stuff
+use super::curl_utils;
+use super::error::RedditError;
+use super::util::{redact_form_body, REDACTED};
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum HttpMethod {
+    GET,
+    POST,
+}
+
+/// Request sent through a `HttpTransport`
+#[derive(PartialEq, Clone)]
+pub struct HttpRequest {
+    pub method: HttpMethod,
+    pub url: String,
+    /// Raw header lines, e.g. `Authorization: bearer <token>`
+    pub headers: Vec<String>,
+    /// Form encoded payload of POST requests
+    pub body: String,
+    /// User agent of the request. Transports fall back to `default_user_agent()` if not set
+    pub user_agent: Option<String>,
+}
+
+impl HttpRequest {
+    /// Creates GET request for `url`
+    pub fn get(url: &str) -> HttpRequest {
+        HttpRequest {
+            method: HttpMethod::GET,
+            url: url.to_owned(),
+            headers: Vec::new(),
+            body: String::new(),
+            user_agent: None,
+        }
+    }
+    /// Creates POST request for `url` sending `body`
+    pub fn post(url: &str, body: &str) -> HttpRequest {
+        HttpRequest {
+            method: HttpMethod::POST,
+            url: url.to_owned(),
+            headers: Vec::new(),
+            body: body.to_owned(),
+            user_agent: None,
+        }
+    }
+    /// Adds raw header line
+    pub fn header(mut self, header: &str) -> HttpRequest {
+        self.headers.push(header.to_owned());
+        self
+    }
+    /// Sets user agent
+    pub fn user_agent(mut self, user_agent: &str) -> HttpRequest {
+        self.user_agent = Some(user_agent.to_owned());
+        self
+    }
+}
+
+impl Debug for HttpRequest {
+    /// Credentials in the `Authorization` header and secret form fields are redacted
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let headers: Vec<String> = self
+            .headers
+            .iter()
+            .map(|header| match header.split_once(':') {
+                Some((name, _)) if name.eq_ignore_ascii_case("authorization") => {
+                    format!("{}: {}", name, REDACTED)
+                }
+                _ => header.to_owned(),
+            })
+            .collect();
+        f.debug_struct("HttpRequest")
+            .field("method", &self.method)
+            .field("url", &self.url)
+            .field("headers", &headers)
+            .field("body", &redact_form_body(&self.body))
+            .field("user_agent", &self.user_agent)
+            .finish()
+    }
+}
+
+/// Response of a `HttpTransport`
+#[derive(Debug, Clone, Default)]
+pub struct HttpResponse {
+    /// HTTP status code, e.g. `200`
+    pub status: u32,
+    /// Response headers. Header names are stored in lowercase
+    pub headers: HashMap<String, String>,
+    /// Raw response body
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// Creates response with `status` and `body`, without any headers
+    pub fn new(status: u32, body: &str) -> HttpResponse {
+        HttpResponse {
+            status,
+            headers: HashMap::new(),
+            body: body.as_bytes().to_vec(),
+        }
+    }
+    /// Returns the value of header `name`. Lookup is case insensitive
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_lowercase()).map(String::as_str)
+    }
+    /// Decodes the body as UTF-8. Invalid sequences are replaced with `U+FFFD`
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+    /// Whether status code is in the range `[200, 300)`
+    pub fn is_success(&self) -> bool {
+        self.status >= 200 && self.status < 300
+    }
+    /// Turns a non-success response into `RedditError::HttpStatus`
+    pub fn error_for_status(self) -> Result<HttpResponse, RedditError> {
+        if self.is_success() {
+            Ok(self)
+        } else {
+            Err(RedditError::HttpStatus {
+                status: self.status,
+                body: self.text(),
+            })
+        }
+    }
+}
+
+/// Sends HTTP requests to reddit
+pub trait HttpTransport: Debug + Send + Sync {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, RedditError>;
+
+    /// Sends a batch of requests. Results are in the same order as `requests`.
+    /// Requests are sent one after another, unless the transport is able to send them in parallel
+    fn send_all(&self, requests: &[HttpRequest]) -> Vec<Result<HttpResponse, RedditError>> {
+        requests.iter().map(|r| self.send(r)).collect()
+    }
+}
+
+/// Default transport, sending requests with curl.
+/// Curl handles are kept in a pool after a request finished, so later requests
+/// reuse their open connections instead of paying for a new TLS handshake
+pub struct CurlTransport {
+    pool: Mutex<Vec<curl_utils::Handle>>,
+    max_parallel: usize,
+}
+
+impl Default for CurlTransport {
+    fn default() -> CurlTransport {
+        CurlTransport {
+            pool: Mutex::new(Vec::new()),
+            max_parallel: 8,
+        }
+    }
+}
+
+impl CurlTransport {
+    /// Sets the maximum number of requests `send_all` runs in parallel, default 8.
+    /// At most this many idle handles are kept in the pool
+    pub fn max_parallel(mut self, max_parallel: usize) -> CurlTransport {
+        self.max_parallel = max_parallel.max(1);
+        self
+    }
+
+    /// Takes `count` handles from the pool, creating new ones if it runs dry
+    fn checkout(&self, count: usize) -> Vec<curl_utils::Handle> {
+        let mut pool = self.pool.lock().unwrap();
+        let reused = pool.len().min(count);
+        let start = pool.len() - reused;
+        let mut handles: Vec<curl_utils::Handle> = pool.drain(start..).collect();
+        handles.extend((reused..count).map(|_| curl_utils::new_handle()));
+        handles
+    }
+
+    /// Puts `handles` back into the pool
+    fn checkin(&self, handles: Vec<curl_utils::Handle>) {
+        let mut pool = self.pool.lock().unwrap();
+        pool.extend(handles);
+        pool.truncate(self.max_parallel);
+    }
+
+    /// Number of idle handles in the pool
+    pub fn idle_handles(&self) -> usize {
+        self.pool.lock().unwrap().len()
+    }
+}
+
+impl Debug for CurlTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("CurlTransport")
+            .field("idle_handles", &self.idle_handles())
+            .field("max_parallel", &self.max_parallel)
+            .finish()
+    }
+}
+
+impl HttpTransport for CurlTransport {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, RedditError> {
+        let mut handles = self.checkout(1);
+        let result = curl_utils::perform(&mut handles[0], request);
+        self.checkin(handles);
+        result
+    }
+
+    /// Sends requests in parallel with curl's multi interface, at most `max_parallel` at once
+    fn send_all(&self, requests: &[HttpRequest]) -> Vec<Result<HttpResponse, RedditError>> {
+        let mut results = Vec::with_capacity(requests.len());
+        for chunk in requests.chunks(self.max_parallel) {
+            let handles = self.checkout(chunk.len());
+            let (handles, chunk_results) = curl_utils::perform_all(handles, chunk);
+            self.checkin(handles);
+            results.extend(chunk_results);
+        }
+        results
+    }
+}
+
+/// Returns the transport used if no other transport is set
+pub fn default_transport() -> Arc<dyn HttpTransport> {
+    Arc::new(CurlTransport::default())
+}
+
+/// In-memory transport for tests.
+/// Answers requests with canned responses in the order they were pushed and records every request
+///
+/// # Example
+/// ```
+/// use std::sync::Arc;
+/// use rsreddit::client::Reddit;
+/// use rsreddit::transport::MockTransport;
+///
+/// let mock = Arc::new(MockTransport::default());
+/// mock.push_response(404, "{}");
+/// let reddit = Reddit::default().transport(mock.clone()).build();
+/// assert!(reddit.thread_by_permalink("/r/rust/comments/abc/").is_err());
+/// assert_eq!(mock.requests()[0].url, "https://www.reddit.com/r/rust/comments/abc/.json");
+/// ```
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    responses: Mutex<VecDeque<Result<HttpResponse, RedditError>>>,
+    requests: Mutex<Vec<HttpRequest>>,
+}
+
+impl MockTransport {
+    /// Queues a response with `status` and `body`
+    pub fn push_response(&self, status: u32, body: &str) {
+        self.push(Ok(HttpResponse::new(status, body)));
+    }
+    /// Queues a complete response or an error, e.g. `RedditError::Transport`
+    pub fn push(&self, response: Result<HttpResponse, RedditError>) {
+        self.responses.lock().unwrap().push_back(response);
+    }
+    /// All requests sent so far
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl HttpTransport for MockTransport {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, RedditError> {
+        self.requests.lock().unwrap().push(request.clone());
+        self.responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_else(|| {
+                Err(RedditError::Transport(format!(
+                    "MockTransport has no response left for {}",
+                    request.url
+                )))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_debug_redacts_secrets() {
+        let request = HttpRequest::post(
+            "https://www.reddit.com/api/v1/access_token",
+            "grant_type=password&password=hunter2&username=spez",
+        )
+        .header("Authorization: Basic YWJjOmh1bnRlcjI=");
+        let debug = format!("{:?}", request);
+        assert!(!debug.contains("hunter2"));
+        assert!(!debug.contains("YWJjOmh1bnRlcjI="));
+        assert!(debug.contains("grant_type=password&password=<redacted>&username=spez"));
+    }
+
+    #[test]
+    fn test_error_for_status() {
+        let response = HttpResponse::new(503, "<html>down</html>");
+        assert_eq!(response.header("Retry-After"), None);
+        match response.error_for_status() {
+            Err(RedditError::HttpStatus { status, body }) => {
+                assert_eq!(status, 503);
+                assert_eq!(body, "<html>down</html>");
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_mock_transport_records_requests() {
+        let mock = MockTransport::default();
+        mock.push_response(200, "first");
+        let request = HttpRequest::post("https://example.com", "a=b").header("X-Test: 1");
+        assert_eq!(mock.send(&request).unwrap().text(), "first");
+        assert!(mock.send(&request).is_err());
+        assert_eq!(mock.requests(), vec![request.clone(), request]);
+    }
+
+    /// Serves `count` requests on a local port, answering with the requested path
+    fn serve(count: usize) -> String {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", server.server_addr());
+        std::thread::spawn(move || {
+            for request in server.incoming_requests().take(count) {
+                let path = request.url().to_string();
+                request
+                    .respond(tiny_http::Response::from_string(path))
+                    .unwrap();
+            }
+        });
+        address
+    }
+
+    #[test]
+    fn test_curl_transport_reuses_handles() {
+        let address = serve(2);
+        let transport = CurlTransport::default();
+        for path in &["/first", "/second"] {
+            let request = HttpRequest::get(&format!("{}{}", address, path));
+            assert_eq!(transport.send(&request).unwrap().text(), *path);
+            assert_eq!(transport.idle_handles(), 1);
+        }
+    }
+
+    #[test]
+    fn test_curl_transport_send_all() {
+        let address = serve(5);
+        let transport = CurlTransport::default().max_parallel(2);
+        let requests: Vec<HttpRequest> = (0..5)
+            .map(|i| HttpRequest::get(&format!("{}/{}", address, i)))
+            .collect();
+        let results = transport.send_all(&requests);
+        for (i, result) in results.iter().enumerate() {
+            let response = result.as_ref().unwrap();
+            assert_eq!(response.status, 200);
+            assert_eq!(response.text(), format!("/{}", i));
+        }
+        assert_eq!(transport.idle_handles(), 2);
+    }
+}
@@ -0,0 +1,72 @@
+//! User agent sent with every request.
+//! Reddit asks for the format `<platform>:<app ID>:<version string> (by /u/<reddit username>)`
+//! See https://github.com/reddit-archive/reddit/wiki/API#rules
+
+//Built in libraries
+use std::fmt;
+
+//Third party libraries
+use serde::{Deserialize, Serialize};
+
+//Own stuff
+use super::VERSION;
+
+/// User agent identifying the application built on this crate
+///
+/// # Example
+/// ```
+/// use rsreddit::user_agent::UserAgent;
+/// let user_agent = UserAgent::new("linux", "my_reddit_bot", "v0.1.0", "my_username");
+/// assert_eq!(user_agent.to_string(), "linux:my_reddit_bot:v0.1.0 (by /u/my_username)");
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserAgent {
+    /// Platform the application runs on, e.g. `linux`
+    pub platform: String,
+    /// Unique identifier of the application, e.g. `com.example.myredditapp`
+    pub app_id: String,
+    pub version: String,
+    /// Reddit username of the developer, without `/u/`
+    pub username: String,
+}
+
+impl UserAgent {
+    pub fn new(platform: &str, app_id: &str, version: &str, username: &str) -> UserAgent {
+        let username = username.trim_start_matches('/').trim_start_matches("u/");
+        UserAgent {
+            platform: platform.to_owned(),
+            app_id: app_id.to_owned(),
+            version: version.to_owned(),
+            username: username.to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for UserAgent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{} (by /u/{})",
+            self.platform, self.app_id, self.version, self.username
+        )
+    }
+}
+
+/// User agent of this crate. Only used if the application does not set its own `UserAgent`
+pub fn default_user_agent() -> String {
+    format!("rsreddit:v{} (by /u/Gitrog_Frog)", VERSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_agent_strips_username_prefix() {
+        let user_agent = UserAgent::new("android", "com.example.app", "v1.2.3", "/u/kemitche");
+        assert_eq!(
+            user_agent.to_string(),
+            "android:com.example.app:v1.2.3 (by /u/kemitche)"
+        );
+    }
+}
@@ -1,39 +1,117 @@
 use rand::distributions::Alphanumeric;
 use rand::{self, Rng};
-use std::collections::HashMap;
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
-use std::hash::Hash;
 use std::string::ToString;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::error::RedditError;
+
+/// Shown in place of secrets and tokens in Debug output
+pub(crate) const REDACTED: &str = "<redacted>";
+/// Form fields carrying passwords, codes or tokens
+const SECRET_FORM_FIELDS: [&str; 5] = ["code", "otp", "password", "refresh_token", "token"];
 
 /// convert map to `query_string`, for example:
 /// convert
-/// `{"redirect_uri":"my_uri",
+/// `{"redirect_uri":"http://127.0.0.1:8000",
 ///  "state":"my-state"
-///  "scope":"test-scope"}`
+///  "scope":"read submit"}`
 /// to
-/// `redirect_uri=my_uri&state=my-state&scope=test-scope`
-/// Since hashmap is not sorted, so the order of key-value-pairs
-/// may differ from times
-pub fn convert_map_to_string<
-    K: Debug + Eq + Hash + ToString,
-    V: Debug + ToString,
-    S: ::std::hash::BuildHasher,
->(
-    map: &HashMap<K, V, S>,
-) -> String {
-    let mut string: String = String::new();
-    for (key, value) in map.iter() {
-        string.push_str(&key.to_string());
-        string.push_str("=");
-        string.push_str(&value.to_string());
-        string.push_str("&");
+/// `redirect_uri=http%3A%2F%2F127.0.0.1%3A8000&scope=read%20submit&state=my-state`
+/// Keys and values are percent-encoded. Pairs are ordered by key, so the result is always the same
+pub fn convert_map_to_string<K: ToString + Ord, V: ToString>(map: &BTreeMap<K, V>) -> String {
+    map.iter()
+        .map(|(key, value)| {
+            format!(
+                "{}={}",
+                url_encode(&key.to_string()),
+                url_encode(&value.to_string())
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+/// Percent-encodes `value` for query strings and `application/x-www-form-urlencoded` bodies.
+/// Only unreserved characters (`A-Z a-z 0-9 - . _ ~`) are kept as they are
+pub fn url_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
     }
-    string
+    encoded
+}
+
+/// Decodes a percent-encoded query string value. `+` is decoded as space.
+/// Invalid escape sequences are kept as they are
+pub fn url_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                match (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                    (Some(high), Some(low)) => {
+                        decoded.push(high * 16 + low);
+                        i += 2;
+                    }
+                    _ => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Value of a hexadecimal digit
+fn hex_value(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|d| d as u8)
+}
+
+/// Parses a query string like `state=abc&code=xyz` into its decoded key-value-pairs.
+/// A leading `?` is ignored. If a key appears more than once, the last value wins
+pub fn parse_query_string(query: &str) -> HashMap<String, String> {
+    query
+        .trim_start_matches('?')
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let mut parts = pair.splitn(2, '=');
+            let key = url_decode(parts.next().unwrap_or_default());
+            let value = url_decode(parts.next().unwrap_or_default());
+            (key, value)
+        })
+        .collect()
+}
+
+/// Replaces the values of secret fields in the form encoded `body`, so it can be logged
+pub(crate) fn redact_form_body(body: &str) -> String {
+    body.split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((key, _)) if SECRET_FORM_FIELDS.contains(&url_decode(key).as_str()) => {
+                format!("{}={}", key, REDACTED)
+            }
+            _ => pair.to_owned(),
+        })
+        .collect::<Vec<String>>()
+        .join("&")
 }
 
 /// Converts vector of objects with ToString Trait to one string with one space as spacer
-pub fn convert_scope_vec_to_string<S: Debug + ToString>(vec: &Vec<S>) -> String {
-    if vec.len() == 0 {
+#[deprecated(note = "Use the `Display` implementation of `scope::ScopeSet` instead")]
+pub fn convert_scope_vec_to_string<S: Debug + ToString>(vec: &[S]) -> String {
+    if vec.is_empty() {
         return "".to_string();
     }
     vec.iter()
@@ -57,22 +135,34 @@ pub fn generate_random_string(length: usize) -> String {
         .collect()
 }
 
+/// Seconds since the unix epoch
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
 /// Checks if first 7 chars are equal to "http://" and removes them if they exist
 pub fn chomp_http_prefix(url: &str) -> String {
-    let slice = &url[..7];
-    let rest = &url[7..];
-    if slice == "http://" {
-        rest.to_string()
-    } else {
-        url.to_string()
-    }
+    url.strip_prefix("http://").unwrap_or(url).to_string()
 }
 
-pub fn open_browser(url: &str) -> Result<(), String> {
-    if !webbrowser::open(url).is_ok() {
-        return Err("Could not open browser. Is a default browser set?".to_owned());
+pub fn open_browser(url: &str) -> Result<(), RedditError> {
+    if webbrowser::open(url).is_err() {
+        return Err(RedditError::Authorization(
+            "Could not open browser. Is a default browser set?".to_owned(),
+        ));
     }
-    return Ok(());
+    Ok(())
+}
+
+/// Deserializes a JSON response body, keeping the raw body around in case of an error
+pub fn parse_json<T: DeserializeOwned>(body: &str) -> Result<T, RedditError> {
+    serde_json::from_str(body).map_err(|e| RedditError::Deserialize {
+        error: e.to_string(),
+        body: body.to_owned(),
+    })
 }
 
 #[cfg(test)]
@@ -81,6 +171,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(deprecated)]
     fn test_convert_string_vec_to_string() {
         let v = vec!["scope1", "scope2", "scope3"];
         let concat = convert_scope_vec_to_string(&v);
@@ -88,9 +179,52 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn test_convert_scope_vec_to_string() {
         let v = vec![RedditApiScope::identity, RedditApiScope::modconfig];
         let concat = convert_scope_vec_to_string(&v);
         assert_eq!("identity modconfig", concat);
     }
+
+    #[test]
+    fn test_parse_json_keeps_raw_body() {
+        let body = "<html>Reddit is down for maintenance</html>";
+        match parse_json::<Vec<String>>(body) {
+            Err(RedditError::Deserialize { body: raw, .. }) => assert_eq!(raw, body),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_url_encode() {
+        assert_eq!(url_encode("a-Z_0.9~"), "a-Z_0.9~");
+        assert_eq!(url_encode("a&b=c+d e\n"), "a%26b%3Dc%2Bd%20e%0A");
+        assert_eq!(url_encode("ü"), "%C3%BC");
+    }
+
+    #[test]
+    fn test_convert_map_to_string() {
+        let mut map = BTreeMap::new();
+        map.insert("state", "my-state");
+        map.insert("redirect_uri", "http://127.0.0.1:8000");
+        map.insert("scope", "read submit");
+        assert_eq!(
+            convert_map_to_string(&map),
+            "redirect_uri=http%3A%2F%2F127.0.0.1%3A8000&scope=read%20submit&state=my-state"
+        );
+        assert_eq!(
+            convert_map_to_string(&BTreeMap::<String, String>::new()),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_parse_query_string() {
+        let query = parse_query_string("?code=a%2Fb+c&state=xyz&flag&broken=%zz%4");
+        assert_eq!(query["code"], "a/b c");
+        assert_eq!(query["state"], "xyz");
+        assert_eq!(query["flag"], "");
+        assert_eq!(query["broken"], "%zz%4");
+        assert_eq!(url_decode(&url_encode("Tom & Jerry ü")), "Tom & Jerry ü");
+    }
 }